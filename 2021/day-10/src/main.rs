@@ -1,107 +1,176 @@
-use pest::Parser;
-
-extern crate pest;
-#[macro_use]
-extern crate pest_derive;
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Bracket {
+    open: char,
+    close: char,
+    error_score: u64,
+    autocomplete_score: u64,
+}
 
-#[derive(Parser)]
-#[grammar = "chunks.pest"]
-struct ChunksParser;
+#[derive(Clone, Debug)]
+struct Brackets(Vec<Bracket>);
 
-struct Chunks<'a> {
-    errors: Vec<(pest::error::Error<Rule>, &'a str)>,
+impl Default for Brackets {
+    fn default() -> Self {
+        Self(vec![
+            Bracket {
+                open: '(',
+                close: ')',
+                error_score: 3,
+                autocomplete_score: 1,
+            },
+            Bracket {
+                open: '[',
+                close: ']',
+                error_score: 57,
+                autocomplete_score: 2,
+            },
+            Bracket {
+                open: '{',
+                close: '}',
+                error_score: 1197,
+                autocomplete_score: 3,
+            },
+            Bracket {
+                open: '<',
+                close: '>',
+                error_score: 25137,
+                autocomplete_score: 4,
+            },
+        ])
+    }
 }
 
-impl<'a> TryFrom<&'a str> for Chunks<'a> {
-    type Error = ();
+impl Brackets {
+    fn opening(&self, c: char) -> Option<usize> {
+        self.0.iter().position(|b| b.open == c)
+    }
 
-    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        let errors = value
-            .lines()
-            .map(|line| (ChunksParser::parse(Rule::chunk_list, line), line))
-            .filter_map(|(res, line)| res.err().map(|err| (err, line)))
-            .collect();
-        Ok(Self { errors })
+    fn closing(&self, c: char) -> Option<usize> {
+        self.0.iter().position(|b| b.close == c)
     }
-}
 
-impl<'a> Chunks<'a> {
-    fn error_score(&self) -> u32 {
-        self.errors
-            .iter()
-            .filter_map(|(err, line)| match err.location {
-                pest::error::InputLocation::Pos(i) => line.chars().nth(i),
-                pest::error::InputLocation::Span(_) => None,
-            })
-            .map(|c| match c {
-                ')' => 3,
-                ']' => 57,
-                '}' => 1197,
-                '>' => 25137,
-                _ => 0,
-            })
-            .sum()
+    fn checker(&self) -> Checker<'_> {
+        Checker {
+            brackets: self,
+            open: vec![],
+            position: 0,
+            errors: vec![],
+        }
     }
 
-    fn incomplete(&self) -> impl Iterator<Item = &str> {
-        self.errors
-            .iter()
-            .filter(|(err, line)| match err.location {
-                pest::error::InputLocation::Pos(i) => line.chars().nth(i).is_none(),
-                pest::error::InputLocation::Span(_) => false,
-            })
-            .map(|(_, line)| *line)
+    fn check(&self, line: &str) -> Checker<'_> {
+        let mut checker = self.checker();
+        checker.extend(line.chars());
+        checker
     }
 
-    fn autocompletes(&self) -> Vec<String> {
-        self.incomplete().map(Self::autocomplete).collect()
+    fn error_score(&self, c: char) -> u64 {
+        self.closing(c).map_or(0, |i| self.0[i].error_score)
     }
 
-    fn autocomplete(input: &str) -> String {
-        let mut close = String::new();
-        let mut open = String::new();
+    fn autocomplete_score(&self, completion: &str) -> u64 {
+        completion.chars().fold(0, |acc, c| {
+            acc * 5 + self.closing(c).map_or(0, |i| self.0[i].autocomplete_score)
+        })
+    }
+}
 
-        for c in input.chars().rev() {
-            match (c, close.chars().last()) {
-                (']' | ')' | '}' | '>', _) => close.push(c),
-                ('[', Some(l)) if l == ']' => {
-                    close.pop();
-                }
-                ('(', Some(l)) if l == ')' => {
-                    close.pop();
-                }
-                ('{', Some(l)) if l == '}' => {
-                    close.pop();
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Corruption {
+    position: usize,
+    expected: Option<char>,
+    found: char,
+}
+
+#[derive(Debug)]
+struct Checker<'a> {
+    brackets: &'a Brackets,
+    open: Vec<usize>,
+    position: usize,
+    errors: Vec<Corruption>,
+}
+
+impl<'a> Checker<'a> {
+    fn push(&mut self, c: char) {
+        if let Some(i) = self.brackets.opening(c) {
+            self.open.push(i);
+        } else if let Some(i) = self.brackets.closing(c) {
+            match self.open.last() {
+                Some(&top) if top == i => {
+                    self.open.pop();
                 }
-                ('<', Some(l)) if l == '>' => {
-                    close.pop();
+                top => {
+                    self.errors.push(Corruption {
+                        position: self.position,
+                        expected: top.map(|&top| self.brackets.0[top].close),
+                        found: c,
+                    });
+                    if let Some(depth) = self.open.iter().rposition(|&open| open == i) {
+                        self.open.truncate(depth);
+                    }
                 }
-                ('[', _) => open.push(']'),
-                ('(', _) => open.push(')'),
-                ('{', _) => open.push('}'),
-                ('<', _) => open.push('>'),
-                _ => {}
             }
         }
-        open
+        self.position += 1;
+    }
+
+    fn errors(&self) -> &[Corruption] {
+        &self.errors
+    }
+
+    fn is_open(&self) -> bool {
+        !self.open.is_empty()
+    }
+
+    fn autocomplete(&self) -> String {
+        self.open
+            .iter()
+            .rev()
+            .map(|&i| self.brackets.0[i].close)
+            .collect()
+    }
+}
+
+impl<'a> Extend<char> for Checker<'a> {
+    fn extend<T: IntoIterator<Item = char>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|c| self.push(c));
+    }
+}
+
+struct Chunks<'a> {
+    brackets: &'a Brackets,
+    lines: Vec<Checker<'a>>,
+}
+
+impl<'a> Chunks<'a> {
+    fn new(input: &str, brackets: &'a Brackets) -> Self {
+        let lines = input.lines().map(|line| brackets.check(line)).collect();
+        Self { brackets, lines }
+    }
+
+    fn error_score(&self) -> u64 {
+        self.lines
+            .iter()
+            .filter_map(|line| line.errors().first())
+            .map(|error| self.brackets.error_score(error.found))
+            .sum()
+    }
+
+    fn incomplete(&self) -> impl Iterator<Item = &Checker<'a>> {
+        self.lines
+            .iter()
+            .filter(|line| line.errors().is_empty() && line.is_open())
+    }
+
+    fn autocompletes(&self) -> Vec<String> {
+        self.incomplete().map(Checker::autocomplete).collect()
     }
 
     fn autocomplete_score(&self) -> u64 {
         let mut scores = self
             .autocompletes()
             .iter()
-            .map(|s| {
-                s.chars().fold(0u64, |acc, c| {
-                    acc * 5
-                        + match c {
-                            ')' => 1,
-                            ']' => 2,
-                            '}' => 3,
-                            '>' => 4,
-                            _ => 0,
-                        }
-                })
-            })
+            .map(|s| self.brackets.autocomplete_score(s))
             .collect::<Vec<u64>>();
         scores.sort();
         scores[scores.len() / 2]
@@ -111,7 +180,8 @@ impl<'a> Chunks<'a> {
 const INPUT: &str = include_str!("input");
 
 fn main() {
-    let chunks = Chunks::try_from(INPUT).expect("parsed input");
+    let brackets = Brackets::default();
+    let chunks = Chunks::new(INPUT, &brackets);
     println!("{}", chunks.error_score());
 
     println!("{}", chunks.autocomplete_score());
@@ -131,8 +201,110 @@ const TEST_INPUT: &str = "[({(<(())[]>[[{[]{<()<>>
 
 #[test]
 fn part_1() {
-    let chunks = Chunks::try_from(TEST_INPUT).expect("parsed input");
+    let brackets = Brackets::default();
+    let chunks = Chunks::new(TEST_INPUT, &brackets);
     assert_eq!(26397, chunks.error_score());
 
     assert_eq!(288957, chunks.autocomplete_score());
 }
+
+#[test]
+fn test_autocompletes() {
+    let brackets = Brackets::default();
+    let chunks = Chunks::new(TEST_INPUT, &brackets);
+    assert_eq!(
+        vec!["}}]])})]", ")}>]})", "}}>}>))))", "]]}}]}]}>", "])}>"],
+        chunks.autocompletes()
+    );
+}
+
+#[test]
+fn test_balanced_lines() {
+    let brackets = Brackets::default();
+    let input = format!("()[]\n{TEST_INPUT}\n<{{}}>");
+    let chunks = Chunks::new(&input, &brackets);
+    assert!(!brackets.check("()[]").is_open());
+    assert_eq!(5, chunks.autocompletes().len());
+    assert_eq!(288957, chunks.autocomplete_score());
+}
+
+#[test]
+fn test_errors() {
+    let brackets = Brackets::default();
+    let checker = brackets.check("{([(<{}[<>[]}>{[]{[(<()>");
+    assert_eq!(
+        Corruption {
+            position: 12,
+            expected: Some(']'),
+            found: '}'
+        },
+        checker.errors()[0]
+    );
+
+    let checker = brackets.check("(]<)>)");
+    assert_eq!(
+        vec![
+            Corruption {
+                position: 1,
+                expected: Some(')'),
+                found: ']'
+            },
+            Corruption {
+                position: 3,
+                expected: Some('>'),
+                found: ')'
+            },
+            Corruption {
+                position: 4,
+                expected: None,
+                found: '>'
+            },
+            Corruption {
+                position: 5,
+                expected: None,
+                found: ')'
+            },
+        ],
+        checker.errors()
+    );
+    assert_eq!("", checker.autocomplete());
+}
+
+#[test]
+fn test_streaming() {
+    let brackets = Brackets(vec![
+        Bracket {
+            open: '/',
+            close: '\\',
+            error_score: 10,
+            autocomplete_score: 1,
+        },
+        Bracket {
+            open: '«',
+            close: '»',
+            error_score: 20,
+            autocomplete_score: 2,
+        },
+    ]);
+    let mut checker = brackets.checker();
+    checker.extend("/«/\\".chars());
+    checker.push('«');
+    assert!(checker.errors().is_empty());
+    assert_eq!("»»\\", checker.autocomplete());
+    assert_eq!(
+        (2 * 5 + 2) * 5 + 1,
+        brackets.autocomplete_score(&checker.autocomplete())
+    );
+
+    checker.extend("\\".chars());
+    assert_eq!(
+        vec![Corruption {
+            position: 5,
+            expected: Some('»'),
+            found: '\\'
+        }],
+        checker.errors()
+    );
+    assert_eq!(10, brackets.error_score(checker.errors()[0].found));
+    assert_eq!("", checker.autocomplete());
+}
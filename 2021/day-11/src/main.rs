@@ -1,10 +1,33 @@
 use ndarray::Array2;
-use std::str::FromStr;
+use std::{collections::VecDeque, iter::from_fn, str::FromStr};
 
 const INPUT: &str = include_str!("input");
 
+type Position = (usize, usize);
+
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+enum Edges {
+    #[default]
+    Bounded,
+    Toroidal,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Step {
+    number: u32,
+    flashed: Vec<Position>,
+}
+
+impl Step {
+    fn flash_count(&self) -> usize {
+        self.flashed.len()
+    }
+}
+
 struct Octopuses {
     map: Array2<u32>,
+    edges: Edges,
     step_count: u32,
 }
 
@@ -12,78 +35,105 @@ impl FromStr for Octopuses {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let map = Array2::from_shape_vec(
-            (10, 10),
-            s.lines()
-                .flat_map(|l| l.chars().filter_map(|c| c.to_digit(10)))
-                .collect(),
-        )
-        .expect("parse octopuses");
+        let rows = s
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.chars().filter_map(|c| c.to_digit(10)).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let width = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != width) {
+            return Err(());
+        }
 
-        Ok(Self { map, step_count: 0 })
+        let map = Array2::from_shape_vec((rows.len(), width), rows.concat()).map_err(|_| ())?;
+
+        Ok(Self {
+            map,
+            edges: Edges::default(),
+            step_count: 0,
+        })
     }
 }
 
 impl Octopuses {
-    fn step(&mut self) -> u32 {
+    #[allow(dead_code)]
+    fn with_edges(self, edges: Edges) -> Self {
+        Self { edges, ..self }
+    }
+
+    fn neighbors(&self, (x, y): Position) -> Vec<Position> {
+        let (rows, cols) = self.map.dim();
+        let shift = |v: usize, d: isize, len: usize| match self.edges {
+            Edges::Bounded => v.checked_add_signed(d).filter(|v| *v < len),
+            Edges::Toroidal => Some((v + len).wrapping_add_signed(d) % len),
+        };
+
+        let mut neighbors = (-1..=1)
+            .flat_map(|dx| (-1..=1).map(move |dy| (dx, dy)))
+            .filter_map(|(dx, dy)| Some((shift(x, dx, rows)?, shift(y, dy, cols)?)))
+            .filter(|pos| *pos != (x, y))
+            .collect::<Vec<_>>();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        neighbors
+    }
+
+    fn step(&mut self) -> Step {
         self.map.iter_mut().for_each(|o| *o += 1);
 
-        let flashing = self
+        let mut queue = self
             .map
             .indexed_iter()
             .filter(|(_, o)| **o > 9)
             .map(|(pos, _)| pos)
-            .collect::<Vec<_>>();
+            .collect::<VecDeque<_>>();
 
-        let count = flashing.iter().map(|pos| self.flash(pos)).sum();
+        let mut flashed = vec![];
+        while let Some(pos) = queue.pop_front() {
+            flashed.push(pos);
+            for neighbor in self.neighbors(pos) {
+                let octopus = &mut self.map[neighbor];
+                *octopus += 1;
+                if *octopus == 10 {
+                    queue.push_back(neighbor);
+                }
+            }
+        }
 
         self.map.iter_mut().filter(|o| **o > 9).for_each(|o| *o = 0);
         self.step_count += 1;
 
-        count
+        Step {
+            number: self.step_count,
+            flashed,
+        }
     }
 
-    fn flash(&mut self, pos: &(usize, usize)) -> u32 {
-        let (x, y) = pos;
-
-        1 + self.increment(&(x.wrapping_sub(1), y.wrapping_sub(1)))
-            + self.increment(&(x.wrapping_sub(1), *y))
-            + self.increment(&(x.wrapping_sub(1), y + 1))
-            + self.increment(&(*x, y.wrapping_sub(1)))
-            + self.increment(&(*x, y + 1))
-            + self.increment(&(x + 1, y.wrapping_sub(1)))
-            + self.increment(&(x + 1, *y))
-            + self.increment(&(x + 1, y + 1))
+    fn steps(&mut self) -> impl Iterator<Item = Step> + '_ {
+        from_fn(move || Some(self.step()))
     }
 
-    fn increment(&mut self, pos: &(usize, usize)) -> u32 {
-        let (x, y) = pos;
-
-        if let Some(octopus) = self.map.get_mut((*x, *y)) {
-            if *octopus == 9 {
-                *octopus += 1;
-                self.flash(pos)
-            } else {
-                *octopus += 1;
-                0
-            }
-        } else {
-            0
-        }
+    fn first_synchronized(&mut self) -> u32 {
+        let len = self.map.len();
+        self.steps()
+            .find(|step| step.flash_count() == len)
+            .expect("octopuses synchronize")
+            .number
     }
 }
 
 fn main() {
     let mut octopuses = INPUT.parse::<Octopuses>().expect("parse octopuses");
 
-    let flash_count = (0..100).map(|_| octopuses.step()).sum::<u32>();
+    let flash_count = octopuses
+        .steps()
+        .take(100)
+        .map(|step| step.flash_count())
+        .sum::<usize>();
 
     println!("{}", flash_count);
 
-    let len = octopuses.map.len();
-    while octopuses.step() != len as u32 {}
-
-    println!("{}", octopuses.step_count);
+    println!("{}", octopuses.first_synchronized());
 }
 
 #[cfg(test)]
@@ -102,7 +152,11 @@ const TEST_INPUT: &str = "5483143223
 fn part_1() {
     let mut octopuses = TEST_INPUT.parse::<Octopuses>().expect("parse octopuses");
 
-    let flash_count = (0..100).map(|_| octopuses.step()).sum::<u32>();
+    let flash_count = octopuses
+        .steps()
+        .take(100)
+        .map(|step| step.flash_count())
+        .sum::<usize>();
 
     assert_eq!(1656, flash_count);
 }
@@ -111,8 +165,63 @@ fn part_1() {
 fn part_2() {
     let mut octopuses = TEST_INPUT.parse::<Octopuses>().expect("parse octopuses");
 
-    let len = octopuses.map.len();
-    while octopuses.step() != len as u32 {}
-
+    assert_eq!(195, octopuses.first_synchronized());
     assert_eq!(195, octopuses.step_count);
 }
+
+#[test]
+fn test_small_grid() {
+    let mut octopuses = "11111\n19991\n19191\n19991\n11111"
+        .parse::<Octopuses>()
+        .expect("parse octopuses");
+
+    let step = octopuses.step();
+    assert_eq!(1, step.number);
+    assert_eq!(9, step.flash_count());
+    assert_eq!(
+        "34543\n40004\n50005\n40004\n34543"
+            .parse::<Octopuses>()
+            .expect("parse octopuses")
+            .map,
+        octopuses.map
+    );
+
+    assert!("123\n45".parse::<Octopuses>().is_err());
+}
+
+#[test]
+fn test_edges() {
+    let input = "90000\n00000\n00000";
+
+    let mut bounded = input.parse::<Octopuses>().expect("parse octopuses");
+    let step = bounded.step();
+    assert_eq!(vec![(0, 0)], step.flashed);
+    assert_eq!(3, bounded.map.iter().filter(|o| **o == 2).count());
+    assert_eq!(1, bounded.map[(2, 4)]);
+
+    let mut toroidal = input
+        .parse::<Octopuses>()
+        .expect("parse octopuses")
+        .with_edges(Edges::Toroidal);
+    let step = toroidal.step();
+    assert_eq!(vec![(0, 0)], step.flashed);
+    assert_eq!(8, toroidal.map.iter().filter(|o| **o == 2).count());
+    assert_eq!(2, toroidal.map[(2, 4)]);
+
+    let mut narrow = "9\n0"
+        .parse::<Octopuses>()
+        .expect("parse octopuses")
+        .with_edges(Edges::Toroidal);
+    narrow.step();
+    assert_eq!(2, narrow.map[(1, 0)]);
+}
+
+#[test]
+fn test_large_grid() {
+    let input = vec!["9".repeat(500); 300].join("\n");
+    let mut octopuses = input.parse::<Octopuses>().expect("parse octopuses");
+
+    let step = octopuses.steps().next().expect("first step");
+    assert_eq!(300 * 500, step.flash_count());
+    assert!(octopuses.map.iter().all(|o| *o == 0));
+}
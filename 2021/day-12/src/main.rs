@@ -3,36 +3,105 @@ use petgraph::{
     graphmap::UnGraphMap,
     EdgeDirection::Outgoing,
 };
-use std::iter::from_fn;
+use std::{collections::HashMap, iter::from_fn};
 
 const INPUT: &str = include_str!("input");
 
+#[derive(Clone, Debug)]
+struct RevisitPolicy<'a> {
+    small_visits: u8,
+    double_visits: u8,
+    forbidden: Vec<&'a str>,
+}
+
+impl<'a> Default for RevisitPolicy<'a> {
+    fn default() -> Self {
+        Self {
+            small_visits: 1,
+            double_visits: 0,
+            forbidden: vec![],
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum CaveError<'a> {
+    /// Two big caves joined directly could be walked between forever.
+    BigCaveEdge(&'a str, &'a str),
+}
+
+/// How often each small cave has been entered, indexed by its slot.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct Visits {
+    counts: Vec<u16>,
+    budget: u8,
+}
+
 struct Cave<'a> {
     graph: UnGraphMap<&'a str, ()>,
+    small: HashMap<&'a str, usize>,
 }
 
 impl<'a> Cave<'a> {
-    fn path_count(&self) -> usize {
-        let start = "start";
-        let stop = "end";
+    const START: &'static str = "start";
+    const END: &'static str = "end";
+
+    fn is_small(cave: &str) -> bool {
+        cave.chars().all(|c| c.is_lowercase())
+    }
+
+    fn visit(&self, policy: &RevisitPolicy, visits: &Visits, cave: &str) -> Option<Visits> {
+        if cave == Self::START || policy.forbidden.contains(&cave) {
+            return None;
+        }
+        let slot = match self.small.get(cave) {
+            Some(slot) => *slot,
+            None => return Some(visits.clone()),
+        };
+        let count = visits.counts[slot];
+        let budget = if count < u16::from(policy.small_visits) {
+            visits.budget
+        } else if count == u16::from(policy.small_visits) && visits.budget > 0 {
+            visits.budget - 1
+        } else {
+            return None;
+        };
+        let mut counts = visits.counts.clone();
+        counts[slot] += 1;
+        Some(Visits { counts, budget })
+    }
+
+    fn start(&self, policy: &RevisitPolicy) -> Visits {
+        Visits {
+            counts: vec![0; self.small.len()],
+            budget: policy.double_visits,
+        }
+    }
 
-        let mut visited: Vec<&str> = vec![start];
-        let mut stack = vec![self.graph.neighbors_directed(start, Outgoing)];
+    fn paths<'p>(
+        &'p self,
+        policy: &'p RevisitPolicy<'a>,
+    ) -> impl Iterator<Item = Vec<&'a str>> + 'p {
+        let mut visited = vec![(Self::START, self.start(policy))];
+        let mut stack = vec![self.graph.neighbors_directed(Self::START, Outgoing)];
 
         from_fn(move || {
             while let Some(children) = stack.last_mut() {
                 if let Some(child) = children.next() {
-                    if child == stop {
+                    let (_, visits) = visited.last().expect("visited caves");
+                    let Some(visits) = self.visit(policy, visits, child) else {
+                        continue;
+                    };
+                    if child == Self::END {
                         let path = visited
                             .iter()
-                            .cloned()
-                            .chain(Some(stop))
+                            .map(|(cave, _)| *cave)
+                            .chain(Some(Self::END))
                             .collect::<Vec<_>>();
                         return Some(path);
-                    } else if !visited.contains(&child) || child.to_uppercase() == child {
-                        visited.push(child);
-                        stack.push(self.graph.neighbors_directed(child, Outgoing));
                     }
+                    visited.push((child, visits));
+                    stack.push(self.graph.neighbors_directed(child, Outgoing));
                 } else {
                     visited.pop();
                     stack.pop();
@@ -40,61 +109,73 @@ impl<'a> Cave<'a> {
             }
             None
         })
-        .count()
     }
 
-    fn twice_path_count(&self) -> usize {
-        let start = "start";
-        let stop = "end";
+    fn count(&self, policy: &RevisitPolicy<'a>) -> usize {
+        let mut memo = HashMap::new();
+        self.count_from(policy, Self::START, self.start(policy), &mut memo)
+    }
 
-        let mut visited: Vec<&str> = vec![start];
-        let mut stack = vec![self.graph.neighbors_directed(start, Outgoing)];
-        let mut small_twice = None;
+    fn count_from(
+        &self,
+        policy: &RevisitPolicy<'a>,
+        cave: &'a str,
+        visits: Visits,
+        memo: &mut HashMap<(&'a str, Visits), usize>,
+    ) -> usize {
+        if cave == Self::END {
+            return 1;
+        }
+        if let Some(count) = memo.get(&(cave, visits.clone())) {
+            return *count;
+        }
+        let count = self
+            .graph
+            .neighbors_directed(cave, Outgoing)
+            .filter_map(|child| {
+                self.visit(policy, &visits, child)
+                    .map(|visits| self.count_from(policy, child, visits, memo))
+            })
+            .sum();
+        memo.insert((cave, visits), count);
+        count
+    }
 
-        from_fn(move || {
-            while let Some(children) = stack.last_mut() {
-                if let Some(child) = children.next() {
-                    if child == stop {
-                        let path = visited
-                            .iter()
-                            .cloned()
-                            .chain(Some(stop))
-                            .collect::<Vec<_>>();
-                        return Some(path);
-                    } else if !visited.contains(&child) || child.to_uppercase() == child {
-                        visited.push(child);
-                        stack.push(self.graph.neighbors_directed(child, Outgoing));
-                    } else if start != child
-                        && small_twice.is_none()
-                        && child.to_uppercase() != child
-                        && visited.contains(&child)
-                    {
-                        visited.push(child);
-                        stack.push(self.graph.neighbors_directed(child, Outgoing));
-                        small_twice = Some(child);
-                    }
-                } else {
-                    stack.pop();
-                    if small_twice == visited.pop() {
-                        small_twice = None;
-                    }
-                }
-            }
-            None
+    fn path_count(&self) -> usize {
+        self.count(&RevisitPolicy::default())
+    }
+
+    fn twice_path_count(&self) -> usize {
+        self.count(&RevisitPolicy {
+            double_visits: 1,
+            ..Default::default()
         })
-        .count()
     }
 }
 
 impl<'a> TryFrom<&'a str> for Cave<'a> {
-    type Error = ();
+    type Error = CaveError<'a>;
 
     fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        let edges = value.lines().filter_map(|l| l.split_once("-"));
+        let edges = value
+            .lines()
+            .filter_map(|l| l.split_once('-'))
+            .collect::<Vec<_>>();
+        if let Some((a, b)) = edges
+            .iter()
+            .find(|(a, b)| !Self::is_small(a) && !Self::is_small(b))
+        {
+            return Err(CaveError::BigCaveEdge(a, b));
+        }
 
         let graph = UnGraphMap::<_, _>::from_edges(edges);
+        let small = graph
+            .nodes()
+            .filter(|cave| Self::is_small(cave) && *cave != Self::START && *cave != Self::END)
+            .zip(0..)
+            .collect();
 
-        Ok(Cave { graph })
+        Ok(Cave { graph, small })
     }
 }
 
@@ -104,6 +185,10 @@ fn main() {
 
     println!("{}", cave.twice_path_count());
 
+    if let Some(path) = cave.paths(&RevisitPolicy::default()).next() {
+        println!("{}", path.join(","));
+    }
+
     println!(
         "{:?}",
         Dot::with_config(&cave.graph, &[Config::EdgeNoLabel])
@@ -141,3 +226,89 @@ fn part_2() {
     let cave = Cave::try_from(TEST_INPUT).expect("parse cave");
     assert_eq!(3509, cave.twice_path_count());
 }
+
+#[test]
+fn test_paths() {
+    let cave = Cave::try_from("start-A\nstart-b\nA-c\nA-b\nb-d\nA-end\nb-end").expect("parse cave");
+    let policy = RevisitPolicy::default();
+    assert_eq!(10, cave.paths(&policy).count());
+    assert_eq!(10, cave.count(&policy));
+    assert!(cave
+        .paths(&policy)
+        .any(|path| path == ["start", "A", "c", "A", "b", "A", "end"]));
+
+    let policy = RevisitPolicy {
+        double_visits: 1,
+        ..Default::default()
+    };
+    assert_eq!(36, cave.paths(&policy).count());
+    assert_eq!(36, cave.count(&policy));
+
+    let policy = RevisitPolicy {
+        forbidden: vec!["c"],
+        ..Default::default()
+    };
+    assert_eq!(5, cave.count(&policy));
+    assert!(cave.paths(&policy).all(|path| !path.contains(&"c")));
+
+    let policy = RevisitPolicy {
+        forbidden: vec![Cave::END],
+        ..Default::default()
+    };
+    assert_eq!(0, cave.count(&policy));
+    assert_eq!(0, cave.paths(&policy).count());
+}
+
+#[test]
+fn test_big_cave_edges() {
+    assert_eq!(
+        Some(CaveError::BigCaveEdge("A", "B")),
+        Cave::try_from("start-A\nA-B\nB-end").err()
+    );
+    assert_eq!(
+        Some(CaveError::BigCaveEdge("A", "A")),
+        Cave::try_from("start-A\nA-A\nA-end").err()
+    );
+    assert!(Cave::try_from("start-A\nA-b\nb-B\nB-end").is_ok());
+}
+
+#[test]
+fn test_many_small_caves() {
+    let input = (0..200)
+        .map(|i| {
+            let name = format!("{}{}", (b'a' + i / 26) as char, (b'a' + i % 26) as char);
+            format!("start-{name}\n{name}-end")
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let cave = Cave::try_from(input.as_str()).expect("parse cave");
+    assert_eq!(200, cave.small.len());
+    assert_eq!(200, cave.path_count());
+    assert_eq!(
+        200,
+        cave.paths(&RevisitPolicy {
+            small_visits: 255,
+            ..Default::default()
+        })
+        .count()
+    );
+}
+
+#[test]
+fn test_policies() {
+    let cave = Cave::try_from(TEST_INPUT).expect("parse cave");
+    for policy in [
+        RevisitPolicy::default(),
+        RevisitPolicy {
+            double_visits: 2,
+            ..Default::default()
+        },
+        RevisitPolicy {
+            small_visits: 2,
+            forbidden: vec!["sl", "DX"],
+            ..Default::default()
+        },
+    ] {
+        assert_eq!(cave.paths(&policy).count(), cave.count(&policy));
+    }
+}
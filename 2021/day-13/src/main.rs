@@ -1,35 +1,138 @@
 use colored::Colorize;
-use std::{collections::HashSet, fmt::Display, str::FromStr};
+use std::{collections::HashSet, fmt::Display, ops::RangeInclusive, str::FromStr};
 use text_io::scan;
 
 const INPUT: &str = include_str!("input");
 
+const LETTER_WIDTH: i32 = 5;
+const LETTERS: [(char, &str); 17] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('I', ".###..#...#...#...#..###"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+type Point = (i32, i32);
+
 struct Thermal {
-    coords: HashSet<(u16, u16)>,
+    coords: HashSet<Point>,
     folds: Vec<Fold>,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum Fold {
-    X(u16),
-    Y(u16),
+    X(i32),
+    Y(i32),
 }
 
-impl Display for Thermal {
+impl Fold {
+    fn apply(&self, (x, y): Point) -> Point {
+        match *self {
+            Fold::X(v) if x > v => (2 * v - x, y),
+            Fold::Y(v) if y > v => (x, 2 * v - y),
+            _ => (x, y),
+        }
+    }
+
+    fn origins(&self, (x, y): Point) -> Vec<Point> {
+        match *self {
+            Fold::X(v) if x < v => vec![(x, y), (2 * v - x, y)],
+            Fold::Y(v) if y < v => vec![(x, y), (x, 2 * v - y)],
+            _ => vec![],
+        }
+    }
+
+    fn fold_range(v: i32, range: &RangeInclusive<i32>) -> RangeInclusive<i32> {
+        *range.start().min(&(2 * v - range.end()))..=v - 1
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Paper {
+    points: HashSet<Point>,
+    x: RangeInclusive<i32>,
+    y: RangeInclusive<i32>,
+}
+
+impl Paper {
+    fn fold(&self, fold: &Fold) -> Self {
+        let points = self.points.iter().map(|p| fold.apply(*p)).collect();
+        let (x, y) = match *fold {
+            Fold::X(v) => (Fold::fold_range(v, &self.x), self.y.clone()),
+            Fold::Y(v) => (self.x.clone(), Fold::fold_range(v, &self.y)),
+        };
+        Self { points, x, y }
+    }
+
+    fn contains(&self, (x, y): Point) -> bool {
+        self.x.contains(&x) && self.y.contains(&y)
+    }
+
+    fn rows(&self) -> impl Iterator<Item = String> + '_ {
+        self.y.clone().map(move |y| {
+            self.x
+                .clone()
+                .map(|x| {
+                    if self.points.contains(&(x, y)) {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect()
+        })
+    }
+
+    fn recognize(&self) -> Option<String> {
+        let rows = self.rows().collect::<Vec<_>>();
+        let width = rows.first()?.len() as i32;
+        (0..(width + 1) / LETTER_WIDTH)
+            .map(|letter| {
+                let start = (letter * LETTER_WIDTH) as usize;
+                let glyph = rows
+                    .iter()
+                    .map(|row| row.get(start..start + 4))
+                    .collect::<Option<String>>()?;
+                LETTERS
+                    .iter()
+                    .find(|(_, pattern)| *pattern == glyph)
+                    .map(|(c, _)| *c)
+            })
+            .collect()
+    }
+}
+
+impl Display for Paper {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.grid_for(self.folds.len())
-            .iter()
-            .for_each(|((x, y), v)| {
-                if x == &0 && y != &0 {
-                    f.write_str("\n").expect("write newline");
+        for row in self.rows() {
+            for c in row.chars() {
+                match c {
+                    '#' => write!(f, "{}", "#".red())?,
+                    _ => write!(f, "{}", "*".green())?,
                 }
-                match v {
-                    '*' => f.write_fmt(format_args!("{}", "*".green())),
-                    '#' => f.write_fmt(format_args!("{}", "#".red())),
-                    _ => Ok(()),
-                }
-                .expect("write value");
-            });
-        f.write_str("\n")
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Thermal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.after(self.folds.len()).fmt(f)
     }
 }
 
@@ -41,11 +144,11 @@ impl FromStr for Thermal {
 
         let coords = coords
             .lines()
-            .filter_map(|l| l.split_once(","))
+            .filter_map(|l| l.split_once(','))
             .map(|(x, y)| {
                 (
-                    x.parse::<u16>().expect("x parse"),
-                    y.parse::<u16>().expect("y parse"),
+                    x.parse::<i32>().expect("x parse"),
+                    y.parse::<i32>().expect("y parse"),
                 )
             })
             .collect::<HashSet<_>>();
@@ -54,7 +157,7 @@ impl FromStr for Thermal {
             .lines()
             .filter_map(|l| {
                 let axis: char;
-                let value: u16;
+                let value: i32;
                 scan!(l.bytes() => "fold along {}={}", axis, value);
                 match axis {
                     'x' => Some(Fold::X(value)),
@@ -62,7 +165,6 @@ impl FromStr for Thermal {
                     _ => None,
                 }
             })
-            .rev()
             .collect::<Vec<_>>();
 
         Ok(Thermal { coords, folds })
@@ -70,74 +172,53 @@ impl FromStr for Thermal {
 }
 
 impl Thermal {
-    fn shape(&self, fold_count: usize) -> (u16, u16) {
-        let take = self.folds.len() - fold_count;
-        let (x, y) = self
-            .folds
-            .iter()
-            .enumerate()
-            .fold((None, None), |mut acc, (index, f)| {
-                match f {
-                    Fold::X(v) => {
-                        if index < take {
-                            acc.0 = Some(v * 2 + 1)
-                        } else if acc.0.is_none() {
-                            acc.0 = Some(*v)
-                        }
-                    }
-                    Fold::Y(v) => {
-                        if index < take {
-                            acc.1 = Some(v * 2 + 1)
-                        } else if acc.1.is_none() {
-                            acc.1 = Some(*v)
-                        }
-                    }
-                }
-                acc
-            });
-        (x.expect("find x shape"), y.expect("find y shape"))
+    fn paper(&self) -> Paper {
+        let max_x = self.coords.iter().map(|(x, _)| *x).max().unwrap_or(0);
+        let max_y = self.coords.iter().map(|(_, y)| *y).max().unwrap_or(0);
+        Paper {
+            points: self.coords.clone(),
+            x: 0..=max_x,
+            y: 0..=max_y,
+        }
     }
 
-    fn grid_for(&self, fold_count: usize) -> Vec<((u16, u16), char)> {
-        let (max_x, max_y) = self.shape(fold_count);
-        let xs = 0..max_x;
-        let ys = 0..max_y;
-        ys.flat_map(|y| xs.clone().map(move |x| (x, y)))
-            .map(|pos| (pos, self.get(pos, fold_count)))
-            .collect::<Vec<_>>()
+    fn papers(&self) -> impl Iterator<Item = Paper> + '_ {
+        self.folds.iter().scan(self.paper(), |paper, fold| {
+            *paper = paper.fold(fold);
+            Some(paper.clone())
+        })
     }
 
-    fn get(&self, pos: (u16, u16), fold_count: usize) -> char {
-        let skip = self.folds.len() - fold_count;
-        let points = self
-            .folds
-            .iter()
-            .skip(skip)
-            .fold(vec![pos], |mut acc, fold| {
-                let mut unfold = acc
-                    .iter()
-                    .map(|(x, y)| match fold {
-                        Fold::X(v) => (v * 2 - x, *y),
-                        Fold::Y(v) => (*x, v * 2 - y),
-                    })
-                    .collect::<Vec<_>>();
-                acc.append(&mut unfold);
-                acc
-            });
-        if points.iter().any(|p| self.coords.contains(p)) {
-            '#'
-        } else {
-            '*'
+    fn after(&self, fold_count: usize) -> Paper {
+        match fold_count {
+            0 => self.paper(),
+            n => self.papers().nth(n - 1).expect("fold count within folds"),
         }
     }
+
+    #[allow(dead_code)]
+    fn origins(&self, pos: Point, fold_count: usize) -> Vec<Point> {
+        let paper = self.paper();
+        self.folds[..fold_count]
+            .iter()
+            .rev()
+            .fold(vec![pos], |acc, fold| {
+                acc.iter().flat_map(|p| fold.origins(*p)).collect()
+            })
+            .into_iter()
+            .filter(|p| paper.contains(*p))
+            .collect()
+    }
 }
 
 fn main() {
     let thermal = INPUT.parse::<Thermal>().expect("parse thermal");
-    let grid = thermal.grid_for(1);
-    println!("{}", grid.iter().filter(|(_, v)| *v == '#').count());
+    println!("{}", thermal.after(1).points.len());
 
     println!("{}", thermal);
+
+    let paper = thermal.after(thermal.folds.len());
+    println!("{}", paper.recognize().unwrap_or_default());
 }
 
 #[cfg(test)]
@@ -166,6 +247,48 @@ fold along x=5";
 #[test]
 fn part_1() {
     let thermal = TEST_INPUT.parse::<Thermal>().expect("parse thermal");
-    let grid = thermal.grid_for(1);
-    assert_eq!(17, grid.iter().filter(|(_, v)| *v == '#').count());
+    assert_eq!(17, thermal.after(1).points.len());
+}
+
+#[test]
+fn test_render() {
+    let thermal = TEST_INPUT.parse::<Thermal>().expect("parse thermal");
+    assert_eq!(
+        vec!["#####", "#...#", "#...#", "#...#", "#####", ".....", "....."],
+        thermal.after(2).rows().collect::<Vec<_>>()
+    );
+    assert_eq!(7, thermal.after(1).rows().count());
+    assert_eq!(15, thermal.after(0).rows().count());
+}
+
+#[test]
+fn test_uneven_fold() {
+    let thermal = "0,0\n1,1\n9,2\n\nfold along x=3"
+        .parse::<Thermal>()
+        .expect("parse thermal");
+    let paper = thermal.after(1);
+    assert_eq!(-3..=2, paper.x);
+    assert_eq!(HashSet::from([(0, 0), (1, 1), (-3, 2)]), paper.points);
+    assert_eq!(vec![(1, 2), (5, 2)], thermal.origins((1, 2), 1));
+    assert_eq!(vec![(9, 2)], thermal.origins((-3, 2), 1));
+}
+
+#[test]
+fn test_origins() {
+    let thermal = TEST_INPUT.parse::<Thermal>().expect("parse thermal");
+    let origins = thermal.origins((0, 0), 2);
+    assert_eq!(4, origins.len());
+    assert!(origins.iter().any(|p| thermal.coords.contains(p)));
+    assert!(origins
+        .iter()
+        .all(|p| thermal.folds.iter().fold(*p, |p, fold| fold.apply(p)) == (0, 0)));
+}
+
+#[test]
+fn test_recognize() {
+    let thermal = INPUT.parse::<Thermal>().expect("parse thermal");
+    assert_eq!(
+        Some("HKUJGAJZ".to_string()),
+        thermal.after(thermal.folds.len()).recognize()
+    );
 }
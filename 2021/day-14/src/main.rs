@@ -1,33 +1,40 @@
-use std::{borrow::Borrow, collections::HashMap, rc::Rc, str::FromStr};
+use std::{collections::HashMap, str::FromStr};
+
+use matrix::Matrix;
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
 
 const INPUT: &str = include_str!("input");
 
 #[derive(Debug)]
 struct Polymerization {
-    pairs: HashMap<PolymerPair, u64>,
-    rules: HashMap<PolymerPair, char>,
-    last_char: char,
+    pairs: HashMap<PolymerPair, BigUint>,
+    rules: HashMap<PolymerPair, u8>,
+    last_element: u8,
 }
 
 impl FromStr for Polymerization {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (pairs, rules) = s.split_once("\n\n").expect("split pairs and rules");
+        let (template, rules) = s.split_once("\n\n").expect("split pairs and rules");
+        let template = template.as_bytes();
 
-        let last_char = pairs.chars().last().expect("last character in pairs");
+        let last_element = *template.last().expect("last element in template");
 
-        let pairs = (0..pairs.len() - 1)
-            .map(|i| (PolymerPair::new(&pairs[i..i + 2]), 1))
-            .collect();
+        let pairs = template.windows(2).fold(HashMap::new(), |mut acc, window| {
+            *acc.entry(PolymerPair::new(window))
+                .or_insert_with(BigUint::zero) += 1u8;
+            acc
+        });
 
         let rules = rules
             .lines()
             .map(|l| {
                 let (pair, insert) = l.split_once(" -> ").expect("split rule and insert");
 
-                let insert = insert.chars().nth(0).expect("first character for insert");
-                let pair = PolymerPair::new(pair);
+                let insert = *insert.as_bytes().first().expect("first element for insert");
+                let pair = PolymerPair::new(pair.as_bytes());
 
                 (pair, insert)
             })
@@ -36,7 +43,7 @@ impl FromStr for Polymerization {
         Ok(Self {
             pairs,
             rules,
-            last_char,
+            last_element,
         })
     }
 }
@@ -47,69 +54,115 @@ impl Polymerization {
             .pairs
             .drain()
             .fold(HashMap::new(), |mut acc, (pair, count)| {
-                let insert = self.rules.get(&pair);
+                let insert = self.rules.get(&pair).copied();
                 pair.expand(insert)
                     .iter()
-                    .for_each(|pair| *acc.entry(pair.clone()).or_default() += count);
+                    .for_each(|pair| *acc.entry(*pair).or_insert_with(BigUint::zero) += &count);
                 acc
             })
     }
 
-    fn min(&self) -> u64 {
-        *self.counts().values().min().unwrap_or(&0)
+    fn min(&self) -> BigUint {
+        Self::least(&self.counts())
+    }
+
+    fn max(&self) -> BigUint {
+        Self::most(&self.counts())
+    }
+
+    fn counts(&self) -> HashMap<char, BigUint> {
+        self.element_counts(self.pairs.iter())
+    }
+
+    fn counts_after(&self, steps: u64) -> HashMap<char, BigUint> {
+        let pairs = self.reachable_pairs();
+        let index = pairs
+            .iter()
+            .enumerate()
+            .map(|(i, pair)| (*pair, i))
+            .collect::<HashMap<_, _>>();
+
+        let mut transition = Matrix::zero(pairs.len());
+        pairs.iter().enumerate().for_each(|(from, pair)| {
+            pair.expand(self.rules.get(pair).copied())
+                .iter()
+                .for_each(|to| transition.0[index[to]][from] += 1u8)
+        });
+
+        let counts = pairs
+            .iter()
+            .map(|pair| self.pairs.get(pair).cloned().unwrap_or_default())
+            .collect::<Vec<_>>();
+        let counts = transition.pow(steps).apply(&counts);
+
+        self.element_counts(pairs.iter().zip(counts.iter()))
     }
 
-    fn max(&self) -> u64 {
-        *self.counts().values().max().unwrap_or(&0)
+    fn spread(counts: &HashMap<char, BigUint>) -> BigUint {
+        Self::most(counts) - Self::least(counts)
     }
 
-    fn counts(&self) -> HashMap<char, u64> {
-        self.pairs.iter().fold(
-            HashMap::from([(self.last_char, 1u64)]),
+    fn least(counts: &HashMap<char, BigUint>) -> BigUint {
+        counts.values().min().cloned().unwrap_or_default()
+    }
+
+    fn most(counts: &HashMap<char, BigUint>) -> BigUint {
+        counts.values().max().cloned().unwrap_or_default()
+    }
+
+    fn element_counts<'a, I>(&self, pairs: I) -> HashMap<char, BigUint>
+    where
+        I: Iterator<Item = (&'a PolymerPair, &'a BigUint)>,
+    {
+        pairs.filter(|(_, count)| !count.is_zero()).fold(
+            HashMap::from([(self.last_element as char, BigUint::one())]),
             |mut acc, (pair, count)| {
                 *acc.entry(pair.first()).or_default() += count;
                 acc
             },
         )
     }
-}
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-struct PolymerPair(Rc<String>);
-
-impl Borrow<str> for PolymerPair {
-    fn borrow(&self) -> &str {
-        (*self.0).borrow()
+    fn reachable_pairs(&self) -> Vec<PolymerPair> {
+        let mut pairs = self.pairs.keys().copied().collect::<Vec<_>>();
+        let mut i = 0;
+        while i < pairs.len() {
+            let pair = pairs[i];
+            pair.expand(self.rules.get(&pair).copied())
+                .into_iter()
+                .for_each(|pair| {
+                    if !pairs.contains(&pair) {
+                        pairs.push(pair);
+                    }
+                });
+            i += 1;
+        }
+        pairs
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct PolymerPair([u8; 2]);
+
 impl PolymerPair {
-    fn new<S>(pair: S) -> Self
-    where
-        S: Into<String>,
-    {
-        Self(Rc::new(pair.into()))
+    fn new(pair: &[u8]) -> Self {
+        Self([pair[0], pair[1]])
     }
 
     fn first(&self) -> char {
-        self.0.chars().nth(0).expect("first character")
+        self.0[0] as char
     }
 
-    fn expand(self, insert: Option<&char>) -> Vec<PolymerPair> {
+    fn expand(self, insert: Option<u8>) -> Vec<PolymerPair> {
         if let Some(insert) = insert {
-            let combined = self.combined(insert);
             vec![
-                PolymerPair::new(combined[0..2].to_owned()),
-                PolymerPair::new(combined[1..3].to_owned()),
+                PolymerPair([self.0[0], insert]),
+                PolymerPair([insert, self.0[1]]),
             ]
         } else {
             vec![self]
         }
     }
-
-    fn combined(&self, insert: &char) -> String {
-        format!("{}{}{}", &self.0[0..1], insert, &self.0[1..2])
-    }
 }
 
 fn main() {
@@ -121,9 +174,9 @@ fn main() {
 
     println!("{}", polymerization.max() - polymerization.min());
 
-    (10..40).for_each(|_| polymerization.step());
+    let counts = polymerization.counts_after(30);
 
-    println!("{}", polymerization.max() - polymerization.min());
+    println!("{}", Polymerization::spread(&counts));
 }
 
 #[cfg(test)]
@@ -152,18 +205,18 @@ fn test_part_1() {
         .parse::<Polymerization>()
         .expect("parse polymerization");
 
-    assert_eq!(1, polymerization.min());
-    assert_eq!(2, polymerization.max());
+    assert_eq!(BigUint::from(1u8), polymerization.min());
+    assert_eq!(BigUint::from(2u8), polymerization.max());
 
     polymerization.step();
 
-    assert_eq!(1, polymerization.min());
-    assert_eq!(2, polymerization.max());
+    assert_eq!(BigUint::from(1u8), polymerization.min());
+    assert_eq!(BigUint::from(2u8), polymerization.max());
 
     (1..10).for_each(|_| polymerization.step());
 
-    assert_eq!(161, polymerization.min());
-    assert_eq!(1749, polymerization.max());
+    assert_eq!(BigUint::from(161u8), polymerization.min());
+    assert_eq!(BigUint::from(1749u16), polymerization.max());
 }
 
 #[test]
@@ -174,6 +227,36 @@ fn test_part_2() {
 
     (0..40).for_each(|_| polymerization.step());
 
-    assert_eq!(3849876073, polymerization.min());
-    assert_eq!(2192039569602, polymerization.max());
+    assert_eq!(BigUint::from(3849876073u64), polymerization.min());
+    assert_eq!(BigUint::from(2192039569602u64), polymerization.max());
+}
+
+#[test]
+fn test_counts_after() {
+    let mut polymerization = TEST_INPUT
+        .parse::<Polymerization>()
+        .expect("parse polymerization");
+
+    assert_eq!(polymerization.counts(), polymerization.counts_after(0));
+
+    let counts = polymerization.counts_after(10);
+    assert_eq!(BigUint::from(1588u16), Polymerization::spread(&counts));
+    assert_eq!(BigUint::from(1749u16), counts[&'B']);
+    assert_eq!(BigUint::from(298u16), counts[&'C']);
+    assert_eq!(BigUint::from(161u8), counts[&'H']);
+    assert_eq!(BigUint::from(865u16), counts[&'N']);
+
+    let counts = polymerization.counts_after(40);
+    assert_eq!(
+        BigUint::from(2188189693529u64),
+        Polymerization::spread(&counts)
+    );
+
+    let counts = polymerization.counts_after(500);
+    (0..500).for_each(|_| polymerization.step());
+    assert_eq!(polymerization.counts(), counts);
+    assert_eq!(
+        (BigUint::from(3u8) << 500) + 1u8,
+        counts.values().sum::<BigUint>()
+    );
 }
@@ -12,7 +12,7 @@ impl FromStr for Cave {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let rows = s.lines().count();
-        let cols = s.lines().nth(0).expect("first line").chars().count();
+        let cols = s.lines().next().expect("first line").chars().count();
         let cave = Array2::from_shape_vec(
             (rows, cols),
             s.lines()
@@ -55,90 +55,144 @@ impl Pos {
     }
 }
 
-impl Cave {
+trait RiskMap {
+    fn get(&self, pos: &Pos) -> Option<usize>;
+
+    fn shape(&self) -> (usize, usize);
+
     fn neighbors(&self, pos: &Pos) -> Vec<(Pos, usize)> {
         vec![pos.up(), pos.down(), pos.left(), pos.right()]
-            .iter()
-            .filter_map(|p| p.as_ref())
-            .filter_map(|p| self.0.get((p.0, p.1)).map(|c| (p.clone(), *c)))
+            .into_iter()
+            .flatten()
+            .filter_map(|p| self.get(&p).map(|c| (p, c)))
             .collect()
     }
 
-    fn shortest_path(&self) -> Option<(Vec<Pos>, usize)> {
-        let shape = self.0.shape();
-        let target = Pos(shape[0] - 1, shape[1] - 1);
-        dijkstra(&Pos(0, 0), |p| self.neighbors(p), |p| *p == target)
+    fn shortest_path(&self) -> Option<Route> {
+        let (rows, cols) = self.shape();
+        let target = Pos(rows - 1, cols - 1);
+        let (path, risk) = dijkstra(&Pos(0, 0), |p| self.neighbors(p), |p| *p == target)?;
+        let steps = path
+            .into_iter()
+            .scan(None, |total: &mut Option<usize>, pos| {
+                let risk = self.get(&pos).expect("path within map");
+                let sum = total.map_or(0, |total| total + risk);
+                *total = Some(sum);
+                Some(Step {
+                    pos,
+                    risk,
+                    total: sum,
+                })
+            })
+            .collect();
+        Some(Route { steps, risk })
     }
 }
 
-struct FullCave(Array2<usize>);
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Step {
+    pos: Pos,
+    risk: usize,
+    total: usize,
+}
 
-impl FullCave {
-    fn new(cave: Cave) -> Self {
-        Self(cave.0)
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Route {
+    steps: Vec<Step>,
+    risk: usize,
+}
+
+impl Route {
+    fn contains(&self, pos: &Pos) -> bool {
+        self.steps.iter().any(|step| step.pos == *pos)
     }
+}
 
+impl RiskMap for Cave {
     fn get(&self, pos: &Pos) -> Option<usize> {
-        let shape = self.0.shape();
-        let rows = shape[0];
-        let cols = shape[1];
+        self.0.get((pos.0, pos.1)).copied()
+    }
 
-        if pos.0 >= rows * 5 || pos.1 >= cols * 5 {
-            return None;
-        }
+    fn shape(&self) -> (usize, usize) {
+        self.0.dim()
+    }
+}
 
-        let inc = pos.0 / rows + pos.1 / cols;
-        let row = pos.0 % rows;
-        let col = pos.1 % cols;
+struct TiledMap<'a, F> {
+    cave: &'a Cave,
+    tiles: (usize, usize),
+    transform: F,
+}
 
-        self.0.get((row, col)).map(|c| {
-            let c = c + inc;
-            if c > 9 {
-                (c + 1) % 10
-            } else {
-                c
-            }
+impl<'a> TiledMap<'a, fn(usize, (usize, usize)) -> usize> {
+    fn wrapping(cave: &'a Cave, tiles: (usize, usize)) -> Self {
+        Self::new(cave, tiles, |risk, (row, col)| {
+            (risk + row + col - 1) % 9 + 1
         })
     }
+}
 
-    fn neighbors(&self, pos: &Pos) -> Vec<(Pos, usize)> {
-        vec![pos.up(), pos.down(), pos.left(), pos.right()]
-            .into_iter()
-            .filter_map(|p| p)
-            .filter_map(|p| self.get(&p).map(|c| (p, c)))
-            .collect()
-    }
-
-    fn shortest_path(&self) -> Option<(Vec<Pos>, usize)> {
-        let shape = self.0.shape();
-        let rows = shape[0];
-        let cols = shape[1];
-        let target = Pos(rows * 5 - 1, cols * 5 - 1);
-        dijkstra(&Pos(0, 0), |p| self.neighbors(p), |p| *p == target)
+impl<'a, F> TiledMap<'a, F>
+where
+    F: Fn(usize, (usize, usize)) -> usize,
+{
+    fn new(cave: &'a Cave, tiles: (usize, usize), transform: F) -> Self {
+        Self {
+            cave,
+            tiles,
+            transform,
+        }
     }
 }
 
-fn main() {
-    let cave = INPUT.parse::<Cave>().expect("parse cave");
-    let result = cave.shortest_path().expect("find shortest path");
-    println!("{}", result.1);
+impl<'a, F> RiskMap for TiledMap<'a, F>
+where
+    F: Fn(usize, (usize, usize)) -> usize,
+{
+    fn get(&self, pos: &Pos) -> Option<usize> {
+        let (rows, cols) = self.cave.shape();
+        let tile = (pos.0 / rows, pos.1 / cols);
+        if tile.0 >= self.tiles.0 || tile.1 >= self.tiles.1 {
+            return None;
+        }
 
-    let full_cave = FullCave::new(cave);
-    let result = full_cave.shortest_path().expect("find shortest path");
+        self.cave
+            .get(&Pos(pos.0 % rows, pos.1 % cols))
+            .map(|risk| (self.transform)(risk, tile))
+    }
+
+    fn shape(&self) -> (usize, usize) {
+        let (rows, cols) = self.cave.shape();
+        (rows * self.tiles.0, cols * self.tiles.1)
+    }
+}
 
-    for row in 0..500 {
-        for col in 0..500 {
-            let out = format!("{}", full_cave.get(&Pos(row, col)).unwrap());
-            if result.0.contains(&Pos(row, col)) {
+fn render(map: &impl RiskMap, route: &Route) {
+    let (rows, cols) = map.shape();
+    for row in 0..rows {
+        for col in 0..cols {
+            let out = format!("{}", map.get(&Pos(row, col)).unwrap());
+            if route.contains(&Pos(row, col)) {
                 print!("{}", out.red());
             } else {
                 print!("{}", out.green());
             }
         }
-        println!("");
+        println!();
     }
+}
 
-    println!("{}", result.1);
+fn main() {
+    let cave = INPUT.parse::<Cave>().expect("parse cave");
+    let route = cave.shortest_path().expect("find shortest path");
+    println!("{}", route.risk);
+
+    let full_cave = TiledMap::wrapping(&cave, (5, 5));
+    let route = full_cave.shortest_path().expect("find shortest path");
+
+    render(&full_cave, &route);
+
+    println!("{}", route.risk);
 }
 
 #[cfg(test)]
@@ -156,40 +210,72 @@ const TEST_INPUT: &str = "1163751742
 #[test]
 fn part_1() {
     let cave = TEST_INPUT.parse::<Cave>().expect("parse cave");
+    let route = cave.shortest_path().expect("find shortest path");
+    assert_eq!(
+        vec![
+            Pos(0, 0),
+            Pos(1, 0),
+            Pos(2, 0),
+            Pos(2, 1),
+            Pos(2, 2),
+            Pos(2, 3),
+            Pos(2, 4),
+            Pos(2, 5),
+            Pos(2, 6),
+            Pos(3, 6),
+            Pos(3, 7),
+            Pos(4, 7),
+            Pos(5, 7),
+            Pos(5, 8),
+            Pos(6, 8),
+            Pos(7, 8),
+            Pos(8, 8),
+            Pos(8, 9),
+            Pos(9, 9)
+        ],
+        route
+            .steps
+            .iter()
+            .map(|step| step.pos.clone())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(40, route.risk);
     assert_eq!(
-        Some((
-            vec![
-                Pos(0, 0),
-                Pos(1, 0),
-                Pos(2, 0),
-                Pos(2, 1),
-                Pos(2, 2),
-                Pos(2, 3),
-                Pos(2, 4),
-                Pos(2, 5),
-                Pos(2, 6),
-                Pos(3, 6),
-                Pos(3, 7),
-                Pos(4, 7),
-                Pos(5, 7),
-                Pos(5, 8),
-                Pos(6, 8),
-                Pos(7, 8),
-                Pos(8, 8),
-                Pos(8, 9),
-                Pos(9, 9)
-            ],
-            40
-        )),
-        cave.shortest_path()
+        Some(&Step {
+            pos: Pos(9, 9),
+            risk: 1,
+            total: 40
+        }),
+        route.steps.last()
     );
+    assert_eq!(0, route.steps[0].total);
 }
 
 #[test]
 fn part_2() {
     let cave = TEST_INPUT.parse::<Cave>().expect("parse cave");
-    let full_cave = FullCave::new(cave);
-    let result = full_cave.shortest_path().expect("find shortest path");
+    let full_cave = TiledMap::wrapping(&cave, (5, 5));
+    let route = full_cave.shortest_path().expect("find shortest path");
+
+    assert_eq!(315, route.risk);
+    assert_eq!((50, 50), full_cave.shape());
+    assert_eq!(Some(2), full_cave.get(&Pos(0, 10)));
+    assert_eq!(Some(9), full_cave.get(&Pos(49, 49)));
+    assert_eq!(None, full_cave.get(&Pos(50, 0)));
+}
 
-    assert_eq!(315, result.1);
+#[test]
+fn test_tiled_map() {
+    let cave = "19\n91".parse::<Cave>().expect("parse cave");
+    let tiled = TiledMap::new(&cave, (1, 3), |risk, (_, col)| risk * (col + 1));
+    assert_eq!((2, 6), tiled.shape());
+    assert_eq!(
+        vec![1, 9, 2, 18, 3, 27],
+        (0..6)
+            .map(|col| tiled.get(&Pos(0, col)).unwrap())
+            .collect::<Vec<_>>()
+    );
+    let route = tiled.shortest_path().expect("find shortest path");
+    assert_eq!(60, route.risk);
+    assert_eq!(route.risk, route.steps.last().unwrap().total);
 }
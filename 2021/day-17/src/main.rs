@@ -1,87 +1,249 @@
-use std::ops::RangeInclusive;
+use std::{ops::RangeInclusive, str::FromStr};
 
+const INPUT: &str = include_str!("input");
+
+type Window = RangeInclusive<i64>;
+
+fn isqrt(n: i128) -> i128 {
+    let mut root = (n as f64).sqrt() as i128;
+    while root * root > n {
+        root -= 1;
+    }
+    while (root + 1) * (root + 1) <= n {
+        root += 1;
+    }
+    root
+}
+
+/// Integer steps `n` where `n² - b·n + c <= 0`.
+fn quadratic_window(b: i128, c: i128) -> Option<RangeInclusive<i128>> {
+    let g = |n: i128| n * n - b * n + c;
+    let discriminant = b * b - 4 * c;
+    if discriminant < 0 {
+        return None;
+    }
+    let root = isqrt(discriminant);
+    let (mut lo, mut hi) = ((b - root).div_euclid(2), (b + root).div_euclid(2) + 1);
+    while g(lo) > 0 && lo <= hi {
+        lo += 1;
+    }
+    while g(hi) > 0 && hi >= lo {
+        hi -= 1;
+    }
+    (lo <= hi).then_some(lo..=hi)
+}
+
+/// Steps `n >= 1` where `n·v - n(n-1)/2` lands within `lo..=hi`.
+fn ballistic(v: i64, lo: i64, hi: i64) -> Vec<Window> {
+    let b = 2 * v as i128 + 1;
+    let reached = match quadratic_window(b, 2 * lo as i128) {
+        Some(window) => window,
+        None => return vec![],
+    };
+    let start = *reached.start().max(&1);
+    let end = *reached.end();
+    let windows = match quadratic_window(b, 2 * hi as i128 + 1) {
+        Some(over) => vec![
+            start..=end.min(over.start() - 1),
+            start.max(over.end() + 1)..=end,
+        ],
+        None => vec![start..=end],
+    };
+    windows
+        .into_iter()
+        .filter(|w| !w.is_empty())
+        .map(|w| *w.start() as i64..=*w.end() as i64)
+        .collect()
+}
+
+fn overlaps(a: &Window, b: &Window) -> bool {
+    a.start().max(b.start()) <= a.end().min(b.end())
+}
+
+#[derive(Debug, PartialEq)]
 struct Target {
-    x: RangeInclusive<i16>,
-    y: RangeInclusive<i16>,
+    x: RangeInclusive<i64>,
+    y: RangeInclusive<i64>,
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let range = |s: &str| -> Result<RangeInclusive<i64>, String> {
+            let (a, b) = s.split_once("..").ok_or(format!("range {}", s))?;
+            let a = a.trim().parse::<i64>().map_err(|e| e.to_string())?;
+            let b = b.trim().parse::<i64>().map_err(|e| e.to_string())?;
+            Ok(a.min(b)..=a.max(b))
+        };
+
+        let (x, y) = s
+            .trim()
+            .strip_prefix("target area: x=")
+            .and_then(|s| s.split_once(", y="))
+            .ok_or(format!("target area {}", s))?;
+
+        Ok(Self {
+            x: range(x)?,
+            y: range(y)?,
+        })
+    }
 }
 
 impl Target {
-    fn max_y(&self) -> i16 {
-        let n = -self.y.start() - 1;
-        n * (n + 1) / 2
+    fn x_steps(&self, vx: i64) -> Vec<Window> {
+        let (v, lo, hi) = if vx < 0 {
+            (-vx, -self.x.end(), -self.x.start())
+        } else {
+            (vx, *self.x.start(), *self.x.end())
+        };
+        let rest = v * (v + 1) / 2;
+
+        let mut windows = ballistic(v, lo, hi)
+            .into_iter()
+            .filter(|w| *w.start() <= v)
+            .map(|w| *w.start()..=*w.end().min(&v))
+            .collect::<Vec<_>>();
+        if (lo..=hi).contains(&rest) {
+            match windows.last_mut() {
+                Some(last) if *last.end() == v => *last = *last.start()..=i64::MAX,
+                _ => windows.push(v.max(1)..=i64::MAX),
+            }
+        }
+        windows
     }
 
-    fn min_x_step(&self) -> i16 {
-        ((*self.x.start() as f64 * 8.0).sqrt() / 2.0 - 0.5).ceil() as i16
+    fn y_steps(&self, vy: i64) -> Vec<Window> {
+        ballistic(vy, *self.y.start(), *self.y.end())
     }
 
-    fn max_x_step(&self) -> i16 {
-        *self.x.end()
+    fn x_velocities(&self) -> RangeInclusive<i64> {
+        *self.x.start().min(&0)..=*self.x.end().max(&0)
     }
 
-    fn min_y_step(&self) -> i16 {
-        *self.y.start()
+    fn y_velocities(&self) -> RangeInclusive<i64> {
+        *self.y.start().min(&0)..=self.y.start().abs().max(self.y.end().abs())
     }
 
-    fn max_y_step(&self) -> i16 {
-        -self.y.start()
+    fn is_unbounded(&self) -> bool {
+        self.y.contains(&0)
+            && self
+                .x_velocities()
+                .any(|vx| self.x_steps(vx).iter().any(|w| *w.end() == i64::MAX))
     }
 
-    fn hits_target(&self, mut xs: i16, mut ys: i16) -> bool {
-        let mut x = xs;
-        let mut y = ys;
-        loop {
-            if self.x.contains(&x) && self.y.contains(&y) {
-                return true;
-            }
-            if self.y.start() > &y || self.x.end() < &x {
-                return false;
-            }
+    fn velocities(&self) -> Option<Vec<(i64, i64)>> {
+        if self.is_unbounded() {
+            return None;
+        }
 
-            if xs > 0 {
-                xs -= 1;
-            }
-            ys -= 1;
+        let xs = self
+            .x_velocities()
+            .map(|vx| (vx, self.x_steps(vx)))
+            .filter(|(_, windows)| !windows.is_empty())
+            .collect::<Vec<_>>();
 
-            x += xs;
-            y += ys;
-        }
+        Some(
+            self.y_velocities()
+                .map(|vy| (vy, self.y_steps(vy)))
+                .filter(|(_, windows)| !windows.is_empty())
+                .flat_map(|(vy, y_windows)| {
+                    xs.iter()
+                        .filter(move |(_, x_windows)| {
+                            x_windows
+                                .iter()
+                                .any(|x| y_windows.iter().any(|y| overlaps(x, y)))
+                        })
+                        .map(move |(vx, _)| (*vx, vy))
+                })
+                .collect(),
+        )
     }
 
-    fn count(&self) -> usize {
-        let xs = self.min_x_step()..=self.max_x_step();
-        let ys = self.min_y_step()..=self.max_y_step();
+    fn max_y(&self) -> Option<i64> {
+        self.velocities()?
+            .iter()
+            .map(|(_, vy)| if *vy > 0 { vy * (vy + 1) / 2 } else { 0 })
+            .max()
+    }
 
-        ys.flat_map(|y| xs.clone().map(move |x| (x, y)))
-            .filter(|(x, y)| self.hits_target(*x, *y))
-            .count()
+    fn count(&self) -> Option<usize> {
+        self.velocities().map(|v| v.len())
     }
 }
 
 fn main() {
-    let target = Target {
-        x: 287..=309,
-        y: -76..=-48,
-    };
-    println!("{}", target.max_y());
-    println!("{}", target.count());
+    let target = INPUT.parse::<Target>().expect("parse target area");
+    println!("{}", target.max_y().expect("highest trick shot"));
+    println!("{}", target.count().expect("bounded velocity count"));
+}
+
+#[cfg(test)]
+fn hits_target(target: &Target, mut xs: i64, mut ys: i64) -> bool {
+    let (mut x, mut y) = (0, 0);
+    for _ in 0..1000 {
+        x += xs;
+        y += ys;
+        xs -= xs.signum();
+        ys -= 1;
+        if target.x.contains(&x) && target.y.contains(&y) {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+const TEST_INPUT: &str = "target area: x=20..30, y=-10..-5";
+
+#[test]
+fn test_parse() {
+    assert_eq!(
+        Target {
+            x: 20..=30,
+            y: -10..=-5,
+        },
+        TEST_INPUT.parse().unwrap()
+    );
+    assert_eq!(
+        Target {
+            x: -30..=-20,
+            y: 5..=10,
+        },
+        "target area: x=-20..-30, y=10..5".parse().unwrap()
+    );
 }
 
 #[test]
 fn test_part_1() {
-    let target = Target {
-        x: 20..=30,
-        y: -10..=-5,
-    };
-    assert_eq!(45, target.max_y())
+    let target = TEST_INPUT.parse::<Target>().unwrap();
+    assert_eq!(Some(45), target.max_y())
 }
 
 #[test]
 fn test_part_2() {
-    let target = Target {
-        x: 20..=30,
-        y: -10..=-5,
-    };
-    assert_eq!(true, target.hits_target(6, 9));
-    assert_eq!(112, target.count())
+    let target = TEST_INPUT.parse::<Target>().unwrap();
+    assert!(target.velocities().unwrap().contains(&(6, 9)));
+    assert_eq!(Some(112), target.count())
+}
+
+#[test]
+fn test_quadrants() {
+    for input in [
+        "target area: x=-30..-20, y=-10..-5",
+        "target area: x=20..30, y=5..10",
+        "target area: x=-30..-20, y=5..10",
+        "target area: x=-3..4, y=-10..-5",
+        "target area: x=-4..3, y=8..12",
+    ] {
+        let target = input.parse::<Target>().unwrap();
+        let expected = (-40..=40)
+            .flat_map(|vx| (-40..=40).map(move |vy| (vx, vy)))
+            .filter(|(vx, vy)| hits_target(&target, *vx, *vy))
+            .count();
+        assert_eq!(Some(expected), target.count(), "{}", input);
+    }
+
+    let target = "target area: x=-2..2, y=-1..1".parse::<Target>().unwrap();
+    assert_eq!(None, target.count());
 }
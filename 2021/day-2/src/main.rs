@@ -1,6 +1,6 @@
 const INPUT: &str = include_str!("input");
 
-#[derive(Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 struct Position {
     horizontal: i32,
     vertical: i32,
@@ -8,25 +8,120 @@ struct Position {
 }
 
 impl Position {
-    fn step(mut self, command: &Command) -> Self {
+    fn product(&self) -> i32 {
+        self.horizontal * self.vertical
+    }
+}
+
+trait MotionModel {
+    fn name(&self) -> &'static str;
+
+    fn step(&self, position: Position, command: &Command) -> Position;
+}
+
+struct Plain;
+
+impl MotionModel for Plain {
+    fn name(&self) -> &'static str {
+        "plain"
+    }
+
+    fn step(&self, mut position: Position, command: &Command) -> Position {
         match command {
-            Command::Forward(unit) => self.horizontal += unit,
-            Command::Up(unit) => self.vertical -= unit,
-            Command::Down(unit) => self.vertical += unit,
+            Command::Forward(unit) => position.horizontal += unit,
+            Command::Up(unit) => position.vertical -= unit,
+            Command::Down(unit) => position.vertical += unit,
         }
-        self
+        position
+    }
+}
+
+struct Aimed;
+
+impl MotionModel for Aimed {
+    fn name(&self) -> &'static str {
+        "aimed"
     }
 
-    fn aimed_step(mut self, command: &Command) -> Self {
+    fn step(&self, mut position: Position, command: &Command) -> Position {
         match command {
             Command::Forward(unit) => {
-                self.horizontal += unit;
-                self.vertical += self.aim * unit;
+                position.horizontal += unit;
+                position.vertical += position.aim * unit;
             }
-            Command::Up(unit) => self.aim -= unit,
-            Command::Down(unit) => self.aim += unit,
+            Command::Up(unit) => position.aim -= unit,
+            Command::Down(unit) => position.aim += unit,
         }
-        self
+        position
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Trace {
+    model: &'static str,
+    positions: Vec<Position>,
+}
+
+impl Trace {
+    fn last(&self) -> Position {
+        *self.positions.last().expect("initial position")
+    }
+}
+
+struct Interpreter<'a> {
+    models: Vec<&'a dyn MotionModel>,
+}
+
+impl<'a> Interpreter<'a> {
+    fn new(models: Vec<&'a dyn MotionModel>) -> Self {
+        Self { models }
+    }
+
+    fn run<'c, I>(&self, commands: I) -> Vec<Trace>
+    where
+        I: IntoIterator<Item = &'c Command>,
+    {
+        let traces = self
+            .models
+            .iter()
+            .map(|model| Trace {
+                model: model.name(),
+                positions: vec![Position::default()],
+            })
+            .collect();
+
+        commands.into_iter().fold(traces, |mut traces, command| {
+            self.models
+                .iter()
+                .zip(traces.iter_mut())
+                .for_each(|(model, trace)| {
+                    let position = model.step(trace.last(), command);
+                    trace.positions.push(position);
+                });
+            traces
+        })
+    }
+
+    fn report(traces: &[Trace]) -> String {
+        traces
+            .iter()
+            .map(|trace| {
+                let position = trace.last();
+                format!(
+                    "{:<8}{:>10}{:>10}{:>12}",
+                    trace.model,
+                    position.horizontal,
+                    position.vertical,
+                    position.product()
+                )
+            })
+            .fold(
+                format!(
+                    "{:<8}{:>10}{:>10}{:>12}",
+                    "model", "forward", "depth", "product"
+                ),
+                |acc, line| acc + "\n" + &line,
+            )
     }
 }
 
@@ -38,7 +133,7 @@ enum Command {
 }
 
 fn parse(line: &str) -> Option<Command> {
-    line.split_once(" ")
+    line.split_once(' ')
         .and_then(|(text, unit)| match (text, unit.parse::<i32>()) {
             ("forward", Ok(unit)) => Some(Command::Forward(unit)),
             ("up", Ok(unit)) => Some(Command::Up(unit)),
@@ -47,22 +142,31 @@ fn parse(line: &str) -> Option<Command> {
         })
 }
 
-fn main() {
-    let commands = INPUT.lines().filter_map(parse).collect::<Vec<_>>();
+fn script(input: &str) -> Result<Vec<Command>, usize> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(number, line)| (number + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| parse(line).ok_or(number))
+        .collect()
+}
 
-    let position = commands
-        .iter()
-        .fold(Position::default(), |acc, c| acc.step(&c));
-    println!("{:?}", position);
-    println!("{}", position.horizontal * position.vertical);
+fn main() {
+    let commands = script(INPUT).expect("parse commands");
 
-    let position = commands
-        .iter()
-        .fold(Position::default(), |acc, c| acc.aimed_step(&c));
-    println!("{:?}", position);
-    println!("{}", position.horizontal * position.vertical);
+    let traces = Interpreter::new(vec![&Plain, &Aimed]).run(&commands);
+    println!("{}", Interpreter::report(&traces));
 }
 
+#[cfg(test)]
+const TEST_INPUT: &str = "forward 5
+down 5
+forward 8
+up 3
+down 8
+forward 2";
+
 #[test]
 fn test_parse() {
     assert_eq!(Some(Command::Forward(1)), parse("forward 1"));
@@ -72,3 +176,41 @@ fn test_parse() {
     assert_eq!(None, parse("garbage"));
     assert_eq!(None, parse("up8"));
 }
+
+#[test]
+fn test_script() {
+    assert_eq!(
+        Ok(vec![Command::Down(2), Command::Forward(3)]),
+        script("# dive\ndown 2\n\n  forward 3  \n")
+    );
+    assert_eq!(Err(3), script("down 2\nup 1\nsideways 4"));
+}
+
+#[test]
+fn test_interpreter() {
+    let commands = script(TEST_INPUT).expect("parse commands");
+    let traces = Interpreter::new(vec![&Plain, &Aimed]).run(&commands);
+
+    assert_eq!(2, traces.len());
+    assert_eq!("plain", traces[0].model);
+    assert_eq!(150, traces[0].last().product());
+    assert_eq!("aimed", traces[1].model);
+    assert_eq!(900, traces[1].last().product());
+
+    assert_eq!(7, traces[1].positions.len());
+    assert_eq!(
+        Position {
+            horizontal: 13,
+            vertical: 40,
+            aim: 5
+        },
+        traces[1].positions[3]
+    );
+
+    assert_eq!(
+        "model      forward     depth     product
+plain           15        10         150
+aimed           15        60         900",
+        Interpreter::report(&traces)
+    );
+}
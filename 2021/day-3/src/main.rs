@@ -2,136 +2,105 @@ use std::cmp::Ordering;
 
 const INPUT: &str = include_str!("input");
 
-#[derive(Default)]
-struct CountedBits {
-    zeros: usize,
-    ones: usize,
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Bit {
+    Zero,
+    One,
 }
 
-impl CountedBits {
-    fn new(input: &Vec<&str>, index: usize) -> Self {
-        input.iter().fold(CountedBits::default(), |mut acc, t| {
-            if t.chars().nth(index).unwrap() == '0' {
-                acc.zeros += 1
-            } else {
-                acc.ones += 1
-            }
-            acc
-        })
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Criteria {
+    MostCommon { tie: Bit },
+    LeastCommon { tie: Bit },
+}
+
+impl Criteria {
+    fn select(&self, zeros: usize, ones: usize) -> Bit {
+        match (self, ones.cmp(&zeros)) {
+            (Criteria::MostCommon { tie } | Criteria::LeastCommon { tie }, Ordering::Equal) => *tie,
+            (Criteria::MostCommon { .. }, Ordering::Greater)
+            | (Criteria::LeastCommon { .. }, Ordering::Less) => Bit::One,
+            _ => Bit::Zero,
+        }
     }
 }
 
 struct Diagnostics {
-    counts: Vec<CountedBits>,
+    words: Vec<u64>,
+    width: usize,
 }
 
 impl Diagnostics {
     fn new(input: &str) -> Self {
         let lines: Vec<&str> = input.lines().collect();
-        let len = lines.first().unwrap().len();
+        let width = lines.first().unwrap().len();
+        assert!(width <= 64, "diagnostic words are at most 64 bits");
 
-        let counts = (0..len)
-            .map(|index| CountedBits::new(&lines, index))
+        let mut words = lines
+            .iter()
+            .map(|line| u64::from_str_radix(line, 2).unwrap())
             .collect::<Vec<_>>();
+        words.sort_unstable();
 
-        Self { counts }
+        Self { words, width }
     }
 
-    fn gamma(&self) -> u16 {
-        self.aggregate(Ordering::Greater)
+    fn mask(&self, index: usize) -> u64 {
+        1 << (self.width - 1 - index)
     }
 
-    fn epsilon(&self) -> u16 {
-        self.aggregate(Ordering::Less)
+    fn gamma(&self) -> u64 {
+        self.aggregate(Criteria::MostCommon { tie: Bit::Zero })
     }
 
-    fn aggregate(&self, order: Ordering) -> u16 {
-        self.counts.iter().fold(0u16, |acc, bit_counts| {
-            if bit_counts.ones.cmp(&bit_counts.zeros) == order {
-                (acc << 1) + 1u16
-            } else {
-                acc << 1
-            }
-        })
+    fn epsilon(&self) -> u64 {
+        self.aggregate(Criteria::LeastCommon { tie: Bit::Zero })
     }
 
-    fn power(&self) -> u32 {
-        self.gamma() as u32 * self.epsilon() as u32
-    }
-}
-
-#[derive(Default)]
-struct GroupedBits<'a> {
-    zeros: Vec<&'a str>,
-    ones: Vec<&'a str>,
-}
-
-impl<'a> GroupedBits<'a> {
-    fn new(input: Vec<&'a str>, index: usize) -> Self {
-        input.iter().fold(GroupedBits::default(), |mut acc, t| {
-            if t.chars().nth(index).unwrap() == '0' {
-                acc.zeros.push(t)
-            } else {
-                acc.ones.push(t)
+    fn aggregate(&self, criteria: Criteria) -> u64 {
+        (0..self.width).fold(0, |acc, index| {
+            let mask = self.mask(index);
+            let ones = self.words.iter().filter(|word| *word & mask != 0).count();
+            match criteria.select(self.words.len() - ones, ones) {
+                Bit::One => acc | mask,
+                Bit::Zero => acc,
             }
-            acc
         })
     }
-}
-
-struct LifeSupport<'a> {
-    lines: Vec<&'a str>,
-    len: usize,
-}
 
-impl<'a> LifeSupport<'a> {
-    fn new(input: &'a str) -> Self {
-        let lines: Vec<&'a str> = input.lines().collect();
-        let len = lines.first().unwrap().len();
-
-        LifeSupport { lines, len }
+    fn power(&self) -> u128 {
+        self.gamma() as u128 * self.epsilon() as u128
     }
 
-    fn oxygen(&self) -> u16 {
-        self.search(|grouped| {
-            if grouped.ones.len() >= grouped.zeros.len() {
-                grouped.ones
-            } else {
-                grouped.zeros
-            }
-        })
+    fn oxygen(&self) -> u64 {
+        self.search(Criteria::MostCommon { tie: Bit::One })
     }
 
-    fn co2(&self) -> u16 {
-        self.search(|grouped| {
-            if grouped.zeros.is_empty() {
-                grouped.ones
-            } else if grouped.ones.is_empty() {
-                grouped.zeros
-            } else if grouped.zeros.len() <= grouped.ones.len() {
-                grouped.zeros
-            } else {
-                grouped.ones
-            }
-        })
+    fn co2(&self) -> u64 {
+        self.search(Criteria::LeastCommon { tie: Bit::Zero })
     }
 
-    fn rating(&self) -> u32 {
-        (self.oxygen() as u32) * (self.co2() as u32)
+    fn life_support(&self) -> u128 {
+        self.oxygen() as u128 * self.co2() as u128
     }
 
-    fn search<F>(&self, mut select: F) -> u16
-    where
-        F: FnMut(GroupedBits<'a>) -> Vec<&'a str>,
-    {
-        let text = (0..self.len)
-            .fold(self.lines.clone(), |acc, index| {
-                select(GroupedBits::new(acc, index))
-            })
-            .first()
-            .unwrap()
-            .clone();
-        u16::from_str_radix(text, 2).unwrap()
+    /// Narrows the sorted words to the range sharing the selected prefix,
+    /// splitting each range where the current bit flips from 0 to 1.
+    fn search(&self, criteria: Criteria) -> u64 {
+        let (lo, _) = (0..self.width).fold((0, self.words.len()), |(lo, hi), index| {
+            if hi - lo <= 1 {
+                return (lo, hi);
+            }
+            let mask = self.mask(index);
+            let split = lo + self.words[lo..hi].partition_point(|word| word & mask == 0);
+            let (zeros, ones) = (split - lo, hi - split);
+            match criteria.select(zeros, ones) {
+                _ if zeros == 0 || ones == 0 => (lo, hi),
+                Bit::Zero => (lo, split),
+                Bit::One => (split, hi),
+            }
+        });
+        self.words[lo]
     }
 }
 
@@ -139,13 +108,11 @@ fn main() {
     let diagnostics = Diagnostics::new(INPUT);
     println!("{}", diagnostics.power());
 
-    let life_support = LifeSupport::new(INPUT);
-    println!("{}", life_support.rating());
+    println!("{}", diagnostics.life_support());
 }
 
-#[test]
-fn test_gamma_and_epsilon() {
-    let input = "00100
+#[cfg(test)]
+const TEST_INPUT: &str = "00100
 11110
 10110
 10111
@@ -157,7 +124,10 @@ fn test_gamma_and_epsilon() {
 11001
 00010
 01010";
-    let diagnostics = Diagnostics::new(input);
+
+#[test]
+fn test_gamma_and_epsilon() {
+    let diagnostics = Diagnostics::new(TEST_INPUT);
     assert_eq!(22, diagnostics.gamma());
     assert_eq!(9, diagnostics.epsilon());
     assert_eq!(198, diagnostics.power());
@@ -165,20 +135,39 @@ fn test_gamma_and_epsilon() {
 
 #[test]
 fn test_life_support() {
-    let input = "00100
-11110
-10110
-10111
-10101
-01111
-00111
-11100
-10000
-11001
-00010
-01010";
-    let life_support = LifeSupport::new(input);
-    assert_eq!(23, life_support.oxygen());
-    assert_eq!(10, life_support.co2());
-    assert_eq!(230, life_support.rating());
+    let diagnostics = Diagnostics::new(TEST_INPUT);
+    assert_eq!(23, diagnostics.oxygen());
+    assert_eq!(10, diagnostics.co2());
+    assert_eq!(230, diagnostics.life_support());
+}
+
+#[test]
+fn test_tie_breaking() {
+    let diagnostics = Diagnostics::new("01\n10\n00");
+    assert_eq!(
+        0b01,
+        diagnostics.search(Criteria::MostCommon { tie: Bit::One })
+    );
+    assert_eq!(
+        0b00,
+        diagnostics.search(Criteria::MostCommon { tie: Bit::Zero })
+    );
+    assert_eq!(
+        0b10,
+        diagnostics.search(Criteria::LeastCommon { tie: Bit::One })
+    );
+    assert_eq!(
+        0b11,
+        diagnostics.aggregate(Criteria::LeastCommon { tie: Bit::One })
+    );
+}
+
+#[test]
+fn test_wide_words() {
+    let input = format!("{}\n{}\n{}", "1".repeat(64), "0".repeat(64), "1".repeat(64));
+    let diagnostics = Diagnostics::new(&input);
+    assert_eq!(u64::MAX, diagnostics.gamma());
+    assert_eq!(0, diagnostics.epsilon());
+    assert_eq!(u64::MAX, diagnostics.oxygen());
+    assert_eq!(0, diagnostics.co2());
 }
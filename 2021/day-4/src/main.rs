@@ -1,131 +1,188 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashMap;
 
 const INPUT: &str = include_str!("input");
 
-const PATTERNS: [u32; 10] = [
-    0b11111_00000_00000_00000_00000,
-    0b00000_11111_00000_00000_00000,
-    0b00000_00000_11111_00000_00000,
-    0b00000_00000_00000_11111_00000,
-    0b00000_00000_00000_00000_11111,
-    0b10000_10000_10000_10000_10000,
-    0b01000_01000_01000_01000_01000,
-    0b00100_00100_00100_00100_00100,
-    0b00010_00010_00010_00010_00010,
-    0b00001_00001_00001_00001_00001
-    // lame bingo game doesn't count diag
-    // 0b10000_01000_00100_00010_00001,
-    // 0b00001_00010_00100_01000_10000,
-];
-
 #[derive(Debug, PartialEq)]
+enum CardError {
+    InvalidNumber(String),
+    WrongSize { size: usize, numbers: usize },
+}
+
+#[derive(Clone, Debug, PartialEq)]
 struct BingoCard {
-    board: HashMap<u8, u8>,
-    marked: u32,
+    size: usize,
+    board: HashMap<u32, usize>,
+    marked: Vec<bool>,
+    patterns: Vec<Vec<usize>>,
 }
 
 impl BingoCard {
-    fn new(input: &str) -> Self {
-        let board = input
+    fn new(input: &str) -> Result<Self, CardError> {
+        let size = input
+            .lines()
+            .map(|l| l.split_whitespace().count())
+            .find(|&count| count > 0)
+            .unwrap_or(0);
+        let numbers = input
             .split_whitespace()
-            .filter_map(|s| s.parse().ok())
+            .map(|s| {
+                s.parse()
+                    .map_err(|_| CardError::InvalidNumber(s.to_string()))
+            })
+            .collect::<Result<Vec<u32>, _>>()?;
+        if numbers.len() != size * size {
+            return Err(CardError::WrongSize {
+                size,
+                numbers: numbers.len(),
+            });
+        }
+
+        let board = numbers
+            .into_iter()
             .enumerate()
-            .map(|a| (a.1, a.0 as u8))
+            .map(|a| (a.1, a.0))
             .collect::<HashMap<_, _>>();
-        BingoCard {
+        let rows = (0..size).map(|row| (0..size).map(|col| row * size + col).collect());
+        let cols = (0..size).map(|col| (0..size).map(|row| row * size + col).collect());
+        Ok(BingoCard {
+            size,
             board,
-            marked: 0u32,
-        }
+            marked: vec![false; size * size],
+            patterns: rows.chain(cols).collect(),
+        })
     }
 
-    fn play(&mut self, draw: u8) {
+    fn with_diagonals(mut self) -> Self {
+        let size = self.size;
+        self.patterns.extend([
+            (0..size).map(|i| i * size + i).collect(),
+            (0..size).map(|i| i * size + size - 1 - i).collect(),
+        ]);
+        self
+    }
+
+    fn play(&mut self, draw: u32) {
         if let Some(index) = self.board.get(&draw) {
-            self.marked |= 1 << index;
+            self.marked[*index] = true;
         }
     }
 
     fn is_winner(&self) -> bool {
-        PATTERNS.iter().any(|p| p & self.marked == *p)
+        self.patterns
+            .iter()
+            .any(|p| p.iter().all(|index| self.marked[*index]))
     }
 
-    fn score(&self, last_num: u8) -> u32 {
+    fn score(&self, last_num: u32) -> u32 {
         let unmarked_sum: u32 = self
             .board
             .iter()
-            .filter(|(_, index)| self.marked & (1u32 << *index) == 0)
-            .map(|(num, _)| *num as u32)
+            .filter(|(_, index)| !self.marked[**index])
+            .map(|(num, _)| *num)
             .sum();
-        unmarked_sum * last_num as u32
+        unmarked_sum * last_num
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Win {
+    draw: usize,
+    card: usize,
+    score: u32,
+}
+
 #[derive(Debug)]
 struct BingoGame {
-    draw: VecDeque<u8>,
+    draw: Vec<u32>,
     cards: Vec<BingoCard>,
-    last_draw: Option<u8>,
-    last_loser: Option<usize>,
 }
 
 impl BingoGame {
-    fn new(input: &str) -> Self {
+    fn new(input: &str) -> Result<Self, CardError> {
         let mut iter = input.split("\n\n");
         let draw = iter
             .next()
             .unwrap_or("")
-            .split(",")
-            .filter_map(|s| s.parse().ok())
+            .split(',')
+            .filter_map(|s| s.trim().parse().ok())
             .collect();
-        let cards = iter.map(BingoCard::new).collect();
-
-        Self {
-            draw,
-            cards,
-            last_draw: None,
-            last_loser: None,
+        let mut cards = iter.filter(|s| !s.trim().is_empty()).collect::<Vec<_>>();
+        if let Some(last) = cards.last_mut() {
+            *last = Self::without_short_tail(last);
         }
-    }
-
-    fn play(&mut self) -> Option<u8> {
-        self.last_draw = self.draw.pop_front();
-        if let Some(draw) = self.last_draw {
-            self.last_loser = self.last_loser_position();
-            self.cards.iter_mut().for_each(|c| c.play(draw));
-        }
-        self.last_draw
-    }
+        let cards = cards
+            .into_iter()
+            .map(BingoCard::new)
+            .collect::<Result<_, _>>()?;
 
-    fn find_winners(&self) -> Vec<&BingoCard> {
-        self.cards.iter().filter(|c| c.is_winner()).collect()
+        Ok(Self { draw, cards })
     }
 
-    fn find_losers(&self) -> Vec<&BingoCard> {
-        self.cards.iter().filter(|c| !c.is_winner()).collect()
+    /// Drops a stray line after the last card's final row, warning about it.
+    fn without_short_tail(card: &str) -> &str {
+        let card = card.trim_end();
+        let width = card
+            .lines()
+            .map(|l| l.split_whitespace().count())
+            .find(|&count| count > 0)
+            .unwrap_or(0);
+        match card.rsplit_once('\n') {
+            Some((rows, tail))
+                if rows.lines().filter(|l| !l.trim().is_empty()).count() == width
+                    && tail.split_whitespace().count() < width =>
+            {
+                eprintln!("Warning: ignoring trailing line '{}'", tail.trim());
+                rows
+            }
+            _ => card,
+        }
     }
 
-    fn last_loser(&self) -> Option<&BingoCard> {
-        self.last_loser.map(|pos| &self.cards[pos])
+    fn with_diagonals(self) -> Self {
+        let cards = self
+            .cards
+            .into_iter()
+            .map(BingoCard::with_diagonals)
+            .collect();
+        Self { cards, ..self }
     }
 
-    fn last_loser_position(&self) -> Option<usize> {
-        self.cards.iter().position(|c| !c.is_winner())
+    fn log(&self) -> Vec<Win> {
+        let mut cards = self.cards.iter().cloned().map(Some).collect::<Vec<_>>();
+        self.draw
+            .iter()
+            .enumerate()
+            .flat_map(|(draw, number)| {
+                cards
+                    .iter_mut()
+                    .enumerate()
+                    .filter_map(|(card, slot)| {
+                        let bingo = slot.as_mut()?;
+                        bingo.play(*number);
+                        if !bingo.is_winner() {
+                            return None;
+                        }
+                        let score = bingo.score(*number);
+                        *slot = None;
+                        Some(Win { draw, card, score })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
     }
 }
 
 fn main() {
-    let mut game = BingoGame::new(INPUT);
-    while game.find_winners().is_empty() && game.play().is_some() {}
-    println!(
-        "{}",
-        game.find_winners()[0].score(game.last_draw.expect("drawn number"))
-    );
+    let game = BingoGame::new(INPUT).expect("bingo game to parse");
+    let log = game.log();
+    println!("{}", log.first().expect("first winner").score);
+    println!("{}", log.last().expect("last winner").score);
 
-    while !game.find_losers().is_empty() && game.play().is_some() {}
+    let log = game.with_diagonals().log();
     println!(
-        "{}",
-        game.last_loser()
-            .expect("last loser")
-            .score(game.last_draw.expect("drawn number"))
+        "with diagonals: {} {}",
+        log.first().expect("first winner").score,
+        log.last().expect("last winner").score
     );
 }
 
@@ -153,16 +210,98 @@ const TEST_INPUT: &str = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,1
 
 #[test]
 fn test_bingo_cards() {
-    let mut game = BingoGame::new(TEST_INPUT);
-
-    let no_winners: Vec<&BingoCard> = vec![];
-    (0..11).into_iter().for_each(|_| {
-        game.play();
-        assert_eq!(no_winners, game.find_winners());
-    });
-
-    game.play();
-    let winners = game.find_winners();
-    assert_eq!(1, winners.len());
-    assert_eq!(4512, winners[0].score(game.last_draw.unwrap()));
+    let log = BingoGame::new(TEST_INPUT).expect("bingo game").log();
+
+    assert_eq!(
+        vec![
+            Win {
+                draw: 11,
+                card: 2,
+                score: 4512
+            },
+            Win {
+                draw: 13,
+                card: 0,
+                score: 2192
+            },
+            Win {
+                draw: 14,
+                card: 1,
+                score: 1924
+            },
+        ],
+        log
+    );
+}
+
+#[test]
+fn test_diagonals() {
+    let input = "1,5,9,3
+
+1 2 3
+4 5 6
+7 8 9
+
+9 8 1
+6 5 4
+3 2 7
+";
+    let game = BingoGame::new(input).expect("bingo game");
+    assert_eq!(Vec::<Win>::new(), game.log());
+    assert_eq!(
+        vec![
+            Win {
+                draw: 2,
+                card: 0,
+                score: 30 * 9
+            },
+            Win {
+                draw: 3,
+                card: 1,
+                score: 27 * 3
+            },
+        ],
+        game.with_diagonals().log()
+    );
+}
+
+#[test]
+fn test_invalid_cards() {
+    assert_eq!(
+        Err(CardError::WrongSize {
+            size: 3,
+            numbers: 8
+        }),
+        BingoCard::new("1 2 3\n4 5 6\n7 8")
+    );
+    assert_eq!(
+        Err(CardError::WrongSize {
+            size: 2,
+            numbers: 6
+        }),
+        BingoCard::new("1 2\n3 4\n5 6")
+    );
+    assert_eq!(
+        Err(CardError::InvalidNumber("x".to_string())),
+        BingoCard::new("1 2\n3 x")
+    );
+    assert!(BingoGame::new("1,2\n\n1 2\n3 4\n\n1 2 3\n4 5 6").is_err());
+    assert_eq!(2, BingoCard::new("1 2\n3 4").map(|c| c.size).unwrap_or(0));
+
+    let game = BingoGame::new("1,2\n\n1 2\n3 4\n\n5 6\n7 8\n9\n").expect("bingo game");
+    assert_eq!(2, game.cards.len());
+    assert_eq!(4, game.cards[1].board.len());
+    assert!(BingoGame::new("1,2\n\n1 2\n3\n\n5 6\n7 8").is_err());
+}
+
+#[test]
+fn test_repeated_diagonals() {
+    let card = BingoCard::new("1 2 3\n4 5 6\n7 8 9")
+        .expect("card")
+        .with_diagonals()
+        .with_diagonals();
+    assert_eq!(3, card.size);
+    let mut card = card;
+    [1, 5, 9].into_iter().for_each(|n| card.play(n));
+    assert!(card.is_winner());
 }
@@ -1,30 +1,56 @@
-use std::collections::HashMap;
+use std::collections::HashSet;
+
+use num_bigint::BigInt;
+use num_traits::Zero;
 
 const INPUT: &str = include_str!("input");
 
-fn range(left: u16, right: u16) -> Vec<u16> {
-    if left > right {
-        (right..=left).rev().collect()
+type Vector = (i128, i128);
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
     } else {
-        (left..=right).collect()
+        gcd(b, a % b)
     }
 }
 
-#[derive(Eq, Debug, Hash, PartialEq)]
+/// Products of offsets can exceed `i128` on sloped lines near the `i64`
+/// limits, so they are taken exactly.
+fn cross(a: Vector, b: Vector) -> BigInt {
+    BigInt::from(a.0) * b.1 - BigInt::from(a.1) * b.0
+}
+
+fn dot(a: Vector, b: Vector) -> BigInt {
+    BigInt::from(a.0) * b.0 + BigInt::from(a.1) * b.1
+}
+
+/// A multiple of a step along a line, which fits `i128` after clamping.
+fn multiple(n: BigInt) -> i128 {
+    i128::try_from(n).expect("step multiple to fit in i128")
+}
+
+#[derive(Clone, Copy, Eq, Debug, Hash, PartialEq)]
 struct Point {
-    x: u16,
-    y: u16,
+    x: i64,
+    y: i64,
 }
 
 impl Point {
     fn new(input: &str) -> Option<Self> {
         input
-            .split_once(",")
-            .map(|(x, y)| match (x.parse::<u16>(), y.parse::<u16>()) {
+            .split_once(',')
+            .and_then(|(x, y)| match (x.trim().parse(), y.trim().parse()) {
                 (Ok(x), Ok(y)) => Some(Self { x, y }),
                 _ => None,
             })
-            .flatten()
+    }
+
+    fn offset(&self, other: &Point) -> Vector {
+        (
+            other.x as i128 - self.x as i128,
+            other.y as i128 - self.y as i128,
+        )
     }
 }
 
@@ -36,73 +62,149 @@ struct Line {
 
 impl Line {
     fn new(input: &str) -> Option<Self> {
-        input
-            .split_once(" -> ")
-            .map(
-                |(start, stop)| match (Point::new(start), Point::new(stop)) {
-                    (Some(start), Some(stop)) => Some(Self { start, stop }),
-                    _ => None,
-                },
-            )
-            .flatten()
-    }
-
-    fn points(&self) -> Vec<Point> {
-        let points = if self.start.x == self.stop.x {
-            range(self.start.y, self.stop.y)
-                .iter()
-                .map(|y| Point {
-                    x: self.start.x,
-                    y: *y,
-                })
-                .collect()
-        } else if self.start.y == self.stop.y {
-            range(self.start.x, self.stop.x)
-                .iter()
-                .map(|x| Point {
-                    x: *x,
-                    y: self.start.y,
-                })
-                .collect()
-        } else {
-            range(self.start.x, self.stop.x)
-                .iter()
-                .zip(range(self.start.y, self.stop.y))
-                .map(|(x, y)| Point { x: *x, y })
-                .collect()
+        input.split_once(" -> ").and_then(|(start, stop)| {
+            match (Point::new(start), Point::new(stop)) {
+                (Some(start), Some(stop)) => Some(Self { start, stop }),
+                _ => None,
+            }
+        })
+    }
+
+    fn is_orthogonal(&self) -> bool {
+        self.start.x == self.stop.x || self.start.y == self.stop.y
+    }
+
+    fn steps(&self) -> i128 {
+        let (dx, dy) = self.start.offset(&self.stop);
+        gcd(dx, dy)
+    }
+
+    fn step(&self) -> Vector {
+        let (dx, dy) = self.start.offset(&self.stop);
+        match self.steps() {
+            0 => (0, 0),
+            steps => (dx / steps, dy / steps),
+        }
+    }
+
+    fn at(&self, n: i128) -> Point {
+        let (dx, dy) = self.step();
+        Point {
+            x: (self.start.x as i128 + n * dx) as i64,
+            y: (self.start.y as i128 + n * dy) as i64,
+        }
+    }
+
+    fn position(&self, point: &Point) -> Option<i128> {
+        let step = self.step();
+        let offset = self.start.offset(point);
+        if step == (0, 0) {
+            return (offset == (0, 0)).then_some(0);
+        }
+        if !cross(step, offset).is_zero() {
+            return None;
+        }
+        let n = multiple(dot(step, offset) / dot(step, step));
+        (0..=self.steps()).contains(&n).then_some(n)
+    }
+
+    fn x_range(&self) -> (i64, i64) {
+        (self.start.x.min(self.stop.x), self.start.x.max(self.stop.x))
+    }
+
+    fn y_range(&self) -> (i64, i64) {
+        (self.start.y.min(self.stop.y), self.start.y.max(self.stop.y))
+    }
+
+    fn intersection(&self, other: &Line) -> Vec<Point> {
+        let (a, b) = (self.step(), other.step());
+        if a == (0, 0) {
+            return other
+                .position(&self.start)
+                .map(|_| self.start)
+                .into_iter()
+                .collect();
+        }
+        if b == (0, 0) {
+            return other.intersection(self);
+        }
+
+        let offset = self.start.offset(&other.start);
+        let denominator = cross(a, b);
+        if denominator.is_zero() {
+            if !cross(offset, a).is_zero() {
+                return vec![];
+            }
+            let (first, last) = (
+                multiple(dot(offset, a) / dot(a, a)),
+                multiple(dot(self.start.offset(&other.stop), a) / dot(a, a)),
+            );
+            let from = first.min(last).max(0);
+            let to = first.max(last).min(self.steps());
+            return (from..=to).map(|n| self.at(n)).collect();
+        }
+
+        let (s, t) = (cross(offset, b), cross(offset, a));
+        if !(&s % &denominator).is_zero() || !(&t % &denominator).is_zero() {
+            return vec![];
+        }
+        let (Ok(s), Ok(t)) = (
+            i128::try_from(s / &denominator),
+            i128::try_from(t / &denominator),
+        ) else {
+            return vec![];
         };
-        points
+        if (0..=self.steps()).contains(&s) && (0..=other.steps()).contains(&t) {
+            vec![self.at(s)]
+        } else {
+            vec![]
+        }
     }
 }
 
 struct Grid {
-    points: HashMap<Point, u8>,
+    lines: Vec<Line>,
 }
 
 impl Grid {
     fn new(input: &str) -> Self {
-        let lines = input.lines().filter_map(Line::new).collect::<Vec<Line>>();
-        let points =
-            lines
+        let mut lines = input.lines().filter_map(Line::new).collect::<Vec<Line>>();
+        lines.sort_by_key(|line| line.x_range().0);
+        Self { lines }
+    }
+
+    fn orthogonal(self) -> Self {
+        let lines = self.lines.into_iter().filter(Line::is_orthogonal).collect();
+        Self { lines }
+    }
+
+    /// Lines are sorted by their left edge, so only the following lines that
+    /// start before this one ends can overlap it.
+    fn overlaps(&self) -> HashSet<Point> {
+        let mut overlaps = HashSet::new();
+        for (i, line) in self.lines.iter().enumerate() {
+            let (_, right) = line.x_range();
+            let (bottom, top) = line.y_range();
+            self.lines[i + 1..]
                 .iter()
-                .map(Line::points)
-                .flatten()
-                .fold(HashMap::new(), |mut acc, point| {
-                    let count = acc.entry(point).or_default();
-                    *count += 1;
-                    acc
-                });
-        Self { points }
+                .take_while(|other| other.x_range().0 <= right)
+                .filter(|other| {
+                    let (other_bottom, other_top) = other.y_range();
+                    other_bottom <= top && bottom <= other_top
+                })
+                .for_each(|other| overlaps.extend(line.intersection(other)));
+        }
+        overlaps
     }
 
     fn count_dangerous(&self) -> usize {
-        self.points.iter().filter(|(_, c)| **c > 1).count()
+        self.overlaps().len()
     }
 }
 
 fn main() {
-    let grid = Grid::new(INPUT);
-    println!("{}", grid.count_dangerous());
+    println!("{}", Grid::new(INPUT).orthogonal().count_dangerous());
+    println!("{}", Grid::new(INPUT).count_dangerous());
 }
 
 #[cfg(test)]
@@ -121,4 +223,85 @@ const TEST_INPUT: &str = "0,9 -> 5,9
 fn test_grid() {
     let grid = Grid::new(TEST_INPUT);
     assert_eq!(12, grid.count_dangerous());
+
+    let grid = Grid::new(TEST_INPUT).orthogonal();
+    assert_eq!(5, grid.count_dangerous());
+}
+
+#[test]
+fn test_overlaps() {
+    let grid = Grid::new(
+        "-4000000000,1 -> 4000000000,1
+0,-4000000000 -> 0,4000000000
+-3,-2 -> 3,2
+9,9 -> 9,9
+0,1 -> 5,1",
+    );
+    assert_eq!(
+        HashSet::from([
+            Point { x: 0, y: 1 },
+            Point { x: 1, y: 1 },
+            Point { x: 2, y: 1 },
+            Point { x: 3, y: 1 },
+            Point { x: 4, y: 1 },
+            Point { x: 5, y: 1 },
+            Point { x: 0, y: 0 },
+        ]),
+        grid.overlaps()
+    );
+
+    let grid = Grid::new("0,0 -> 4,6\n0,6 -> 4,0\n0,1 -> 4,7\n2,2 -> 2,2\n6,9 -> 2,3");
+    assert_eq!(
+        HashSet::from([Point { x: 2, y: 3 }, Point { x: 4, y: 6 },]),
+        grid.overlaps()
+    );
+}
+
+#[test]
+fn test_extreme_coordinates() {
+    let grid = Grid::new(
+        "-9000000000000000000,0 -> 9000000000000000000,0
+0,-9000000000000000000 -> 0,9000000000000000000
+-9000000000000000000,-9000000000000000000 -> 9000000000000000000,9000000000000000000
+9223372036854775807,5 -> 9223372036854775807,5",
+    );
+    assert_eq!(HashSet::from([Point { x: 0, y: 0 }]), grid.overlaps());
+
+    let line = Line::new("-9000000000000000000,0 -> 9000000000000000000,0").expect("line");
+    assert_eq!(18_000_000_000_000_000_000, line.steps());
+    assert_eq!((1, 0), line.step());
+    assert_eq!(
+        Some(9_000_000_000_000_000_000),
+        line.position(&Point { x: 0, y: 0 })
+    );
+}
+
+#[test]
+fn test_extreme_slopes() {
+    let line =
+        "-9000000000000000000,-9000000000000000000 -> 9000000000000000000,8999999999999999999";
+    let grid = Grid::new(&format!("{line}\n{line}"));
+    assert_eq!(
+        HashSet::from([
+            Point {
+                x: -9_000_000_000_000_000_000,
+                y: -9_000_000_000_000_000_000
+            },
+            Point {
+                x: 9_000_000_000_000_000_000,
+                y: 8_999_999_999_999_999_999
+            },
+        ]),
+        grid.overlaps()
+    );
+
+    let crossing = Grid::new(&format!(
+        "{line}\n-9000000000000000000,8999999999999999999 -> 9000000000000000000,-9000000000000000000"
+    ));
+    assert_eq!(HashSet::new(), crossing.overlaps());
+
+    let line = Line::new(line).expect("line");
+    assert_eq!(1, line.steps());
+    assert_eq!(Some(1), line.position(&line.stop));
+    assert_eq!(None, line.position(&Point { x: 0, y: 0 }));
 }
@@ -1,5 +1,8 @@
-use std::{collections::HashMap, str::FromStr};
+use std::str::FromStr;
 
+use matrix::Matrix;
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
 use pest::Parser;
 
 const INPUT: &str = include_str!("input");
@@ -12,26 +15,105 @@ extern crate pest_derive;
 #[grammar = "lanternfish.pest"]
 pub struct LanternFishParser;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Species {
+    reset: usize,
+    newborn: usize,
+}
+
+impl Default for Species {
+    fn default() -> Self {
+        Self {
+            reset: 6,
+            newborn: 8,
+        }
+    }
+}
+
+impl Species {
+    fn transition(&self, size: usize) -> Matrix {
+        let mut matrix = Matrix::zero(size);
+        (1..size).for_each(|timer| matrix.0[timer - 1][timer] = BigUint::one());
+        matrix.0[self.reset][0] += 1u8;
+        matrix.0[self.newborn][0] += 1u8;
+        matrix
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Shoal {
+    species: Species,
+    timers: Vec<BigUint>,
+}
+
+impl Shoal {
+    fn new<I>(species: Species, ages: I) -> Self
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let ages = ages.into_iter().collect::<Vec<_>>();
+        let size = ages
+            .iter()
+            .copied()
+            .chain([species.reset, species.newborn])
+            .max()
+            .unwrap_or_default()
+            + 1;
+
+        let timers = ages
+            .into_iter()
+            .fold(vec![BigUint::zero(); size], |mut acc, age| {
+                acc[age] += 1u8;
+                acc
+            });
+
+        Self { species, timers }
+    }
+
+    fn step(&mut self) {
+        let spawning = self.timers.remove(0);
+        self.timers.push(BigUint::zero());
+        self.timers[self.species.reset] += &spawning;
+        self.timers[self.species.newborn] += spawning;
+    }
+
+    fn count(&self) -> BigUint {
+        self.timers.iter().sum()
+    }
+
+    fn count_at(&self, days: u64) -> BigUint {
+        self.species
+            .transition(self.timers.len())
+            .pow(days)
+            .apply(&self.timers)
+            .into_iter()
+            .sum()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
 struct Fishies {
-    fishies: HashMap<i8, u64>,
+    shoals: Vec<Shoal>,
 }
 
 impl Fishies {
+    fn add<I>(&mut self, species: Species, ages: I)
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        self.shoals.push(Shoal::new(species, ages));
+    }
+
     fn step(&mut self) {
-        self.fishies = self
-            .fishies
-            .iter()
-            .map(|(age, count)| (age - 1, *count))
-            .collect();
-        if let Some(count) = self.fishies.remove(&-1) {
-            *self.fishies.entry(6).or_insert(0) += count;
-            *self.fishies.entry(8).or_insert(0) += count;
-        }
+        self.shoals.iter_mut().for_each(Shoal::step);
     }
 
-    fn count(&self) -> u64 {
-        self.fishies.values().sum()
+    fn count(&self) -> BigUint {
+        self.shoals.iter().map(Shoal::count).sum()
+    }
+
+    fn count_at(&self, days: u64) -> BigUint {
+        self.shoals.iter().map(|shoal| shoal.count_at(days)).sum()
     }
 }
 
@@ -42,17 +124,14 @@ impl FromStr for Fishies {
         let pairs =
             LanternFishParser::parse(Rule::fish_list, s).expect("parsed lantern fish input");
 
-        let fishies = pairs
-            .filter_map(|pair| match pair.as_rule() {
-                Rule::fish => pair.as_str().parse::<i8>().ok(),
-                _ => None,
-            })
-            .fold(HashMap::new(), |mut acc, age| {
-                *acc.entry(age).or_insert(0) += 1;
-                acc
-            });
+        let ages = pairs
+            .filter(|pair| pair.as_rule() == Rule::fish)
+            .map(|pair| pair.as_str().parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(Fishies { fishies })
+        let mut fishies = Fishies::default();
+        fishies.add(Species::default(), ages);
+        Ok(fishies)
     }
 }
 
@@ -60,8 +139,7 @@ fn main() {
     let mut fishies = INPUT.parse::<Fishies>().unwrap();
     (0..80).for_each(|_| fishies.step());
     println!("{}", fishies.count());
-    (80..256).for_each(|_| fishies.step());
-    println!("{}", fishies.count());
+    println!("{}", fishies.count_at(256 - 80));
 }
 
 #[cfg(test)]
@@ -71,14 +149,55 @@ const TEST_INPUT: &str = "3,4,3,1,2";
 fn test_part_1() {
     let mut fishies = TEST_INPUT.parse::<Fishies>().unwrap();
     (0..18).for_each(|_| fishies.step());
-    assert_eq!(26, fishies.count());
+    assert_eq!(BigUint::from(26u8), fishies.count());
     (18..80).for_each(|_| fishies.step());
-    assert_eq!(5934, fishies.count());
+    assert_eq!(BigUint::from(5934u16), fishies.count());
 }
 
 #[test]
 fn test_part_2() {
     let mut fishies = TEST_INPUT.parse::<Fishies>().unwrap();
     (0..256).for_each(|_| fishies.step());
-    assert_eq!(26984457539, fishies.count());
+    assert_eq!(BigUint::from(26984457539u64), fishies.count());
+}
+
+#[test]
+fn test_count_at() {
+    let fishies = TEST_INPUT.parse::<Fishies>().unwrap();
+    assert_eq!(BigUint::from(5u8), fishies.count_at(0));
+    assert_eq!(BigUint::from(26u8), fishies.count_at(18));
+    assert_eq!(BigUint::from(5934u16), fishies.count_at(80));
+    assert_eq!(BigUint::from(26984457539u64), fishies.count_at(256));
+
+    let mut stepped = fishies.clone();
+    (0..1000).for_each(|_| stepped.step());
+    assert_eq!(stepped.count(), fishies.count_at(1000));
+    assert!(fishies.count_at(1000) > BigUint::from(u128::MAX));
+}
+
+#[test]
+fn test_species() {
+    let mut fishies = Fishies::default();
+    fishies.add(Species::default(), [3, 4, 3, 1, 2]);
+    fishies.add(
+        Species {
+            reset: 2,
+            newborn: 4,
+        },
+        [0, 1],
+    );
+
+    let mut stepped = fishies.clone();
+    (0..50).for_each(|_| stepped.step());
+    assert_eq!(stepped.count(), fishies.count_at(50));
+
+    let mut fishies = Fishies::default();
+    fishies.add(
+        Species {
+            reset: 0,
+            newborn: 0,
+        },
+        [0],
+    );
+    assert_eq!(BigUint::one() << 100, fishies.count_at(100));
 }
@@ -1,3 +1,5 @@
+use std::ops::RangeInclusive;
+
 use nom::{
     bytes::complete::tag, character::complete::digit1, combinator::map_res, multi::separated_list1,
     IResult,
@@ -5,36 +7,118 @@ use nom::{
 
 const INPUT: &str = include_str!("input");
 
+trait Cost {
+    fn cost(&self, distance: i64) -> i64;
+
+    fn candidates(&self, crabbies: &[i64]) -> RangeInclusive<i64> {
+        let min = crabbies.iter().min().copied().unwrap_or_default();
+        let max = crabbies.iter().max().copied().unwrap_or_default();
+        min..=max
+    }
+}
+
+impl<F> Cost for F
+where
+    F: Fn(i64) -> i64,
+{
+    fn cost(&self, distance: i64) -> i64 {
+        self(distance)
+    }
+}
+
+struct Linear;
+
+impl Cost for Linear {
+    fn cost(&self, distance: i64) -> i64 {
+        distance
+    }
+
+    fn candidates(&self, crabbies: &[i64]) -> RangeInclusive<i64> {
+        let mut sorted = crabbies.to_vec();
+        sorted.sort_unstable();
+        match sorted.len() {
+            0 => 0..=0,
+            n => sorted[(n - 1) / 2]..=sorted[n / 2],
+        }
+    }
+}
+
+struct Triangular;
+
+impl Cost for Triangular {
+    fn cost(&self, distance: i64) -> i64 {
+        distance * (distance + 1) / 2
+    }
+
+    fn candidates(&self, crabbies: &[i64]) -> RangeInclusive<i64> {
+        let (floor, ceil) = mean(crabbies);
+        floor - 1..=ceil + 1
+    }
+}
+
+#[allow(dead_code)]
+struct Quadratic;
+
+impl Cost for Quadratic {
+    fn cost(&self, distance: i64) -> i64 {
+        distance * distance
+    }
+
+    fn candidates(&self, crabbies: &[i64]) -> RangeInclusive<i64> {
+        let (floor, ceil) = mean(crabbies);
+        floor..=ceil
+    }
+}
+
+fn mean(crabbies: &[i64]) -> (i64, i64) {
+    let n = crabbies.len().max(1) as i64;
+    let sum = crabbies.iter().sum::<i64>();
+    (sum.div_euclid(n), (sum + n - 1).div_euclid(n))
+}
+
+#[derive(Debug, PartialEq)]
+struct Alignment {
+    position: i64,
+    fuel: i64,
+}
+
 struct Crabbies {
-    crabbies: Vec<i32>,
+    crabbies: Vec<i64>,
 }
 
 impl Crabbies {
-    fn median(&self) -> i32 {
-        self.crabbies[self.crabbies.len() / 2]
+    fn fuel<C: Cost>(&self, cost: &C, position: i64) -> i64 {
+        self.crabbies
+            .iter()
+            .map(|i| cost.cost((position - i).abs()))
+            .sum()
     }
 
-    fn min_fuel(&self) -> i32 {
-        let median = self.median();
-        self.crabbies.iter().map(|i| (median - i).abs()).sum()
+    /// Binary searches the slope of the total fuel, which is convex for
+    /// any convex cost function, within the candidate positions.
+    fn align<C: Cost>(&self, cost: &C) -> Alignment {
+        let candidates = cost.candidates(&self.crabbies);
+        let (mut lo, mut hi) = (*candidates.start(), *candidates.end());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.fuel(cost, mid + 1) >= self.fuel(cost, mid) {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        Alignment {
+            position: lo,
+            fuel: self.fuel(cost, lo),
+        }
     }
 
-    fn revised_min_fuel(&self) -> i32 {
-        let min = *self.crabbies.first().unwrap();
-        let max = *self.crabbies.last().unwrap();
-
-        (min..max)
-            .map(|position| {
-                self.crabbies
-                    .iter()
-                    .map(|i| {
-                        let n = (position - i).abs() as f32;
-                        (n * (1.0 + n) / 2.0).round() as i32
-                    })
-                    .sum::<i32>()
-            })
-            .min()
-            .unwrap()
+    fn min_fuel(&self) -> i64 {
+        self.align(&Linear).fuel
+    }
+
+    fn revised_min_fuel(&self) -> i64 {
+        self.align(&Triangular).fuel
     }
 }
 
@@ -42,18 +126,17 @@ impl<'a> TryFrom<&'a str> for Crabbies {
     type Error = nom::Err<nom::error::Error<&'a str>>;
 
     fn try_from(s: &'a str) -> Result<Self, Self::Error> {
-        let result: IResult<&str, Vec<i32>> =
-            separated_list1(tag(","), map_res(digit1, |s: &str| s.parse::<i32>()))(s);
+        let result: IResult<&str, Vec<i64>> =
+            separated_list1(tag(","), map_res(digit1, |s: &str| s.parse::<i64>()))(s);
 
-        let (remaining, mut crabbies) = result?;
-        if remaining.len() > 0 {
+        let (remaining, crabbies) = result?;
+        if !remaining.is_empty() {
             println!(
                 "Warning: {} bytes remaining: '{}'",
                 remaining.len(),
                 remaining.replace("\n", "\\n")
             );
         }
-        crabbies.sort();
         Ok(Self { crabbies })
     }
 }
@@ -73,3 +156,43 @@ fn part_1() {
     let crabbies = Crabbies::try_from(TEST_INPUT).expect("parse input");
     assert_eq!(37, crabbies.min_fuel());
 }
+
+#[test]
+fn part_2() {
+    let crabbies = Crabbies::try_from(TEST_INPUT).expect("parse input");
+    assert_eq!(168, crabbies.revised_min_fuel());
+}
+
+#[test]
+fn test_align() {
+    let crabbies = Crabbies::try_from(TEST_INPUT).expect("parse input");
+    assert_eq!(
+        Alignment {
+            position: 2,
+            fuel: 37
+        },
+        crabbies.align(&Linear)
+    );
+    assert_eq!(
+        Alignment {
+            position: 5,
+            fuel: 168
+        },
+        crabbies.align(&Triangular)
+    );
+    assert_eq!(
+        Alignment {
+            position: 5,
+            fuel: 291
+        },
+        crabbies.align(&Quadratic)
+    );
+    assert_eq!(crabbies.align(&Linear), crabbies.align(&|d: i64| d));
+    assert_eq!(
+        Alignment {
+            position: 6,
+            fuel: 2179
+        },
+        crabbies.align(&|d: i64| d * d * d)
+    );
+}
@@ -1,4 +1,5 @@
-use itertools::Itertools;
+use std::collections::BTreeSet;
+
 use pest::Parser;
 
 extern crate pest;
@@ -11,32 +12,124 @@ pub struct SegmentSequnceParser;
 
 const INPUT: &str = include_str!("input");
 
-mod Segments {
-    pub const A: u8 = 0b0000_0001;
-    pub const B: u8 = 0b0000_0010;
-    pub const C: u8 = 0b0000_0100;
-    pub const D: u8 = 0b0000_1000;
-    pub const E: u8 = 0b0001_0000;
-    pub const F: u8 = 0b0010_0000;
-    pub const G: u8 = 0b0100_0000;
-
-    pub fn as_vec() -> Vec<u8> {
-        vec![A, B, C, D, E, F, G]
+#[derive(Debug, PartialEq)]
+enum SolveError {
+    NoSolution,
+    Ambiguous,
+    TooManyWires(usize),
+    UnknownLabel(char),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Wiring(Vec<usize>);
+
+impl Wiring {
+    fn translate(&self, pattern: u32) -> u32 {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(wire, _)| pattern & (1 << wire) != 0)
+            .fold(0, |acc, (_, target)| acc | 1 << target)
     }
 }
 
-const DIGITS: [u8; 10] = [
-    Segments::A | Segments::B | Segments::C | Segments::E | Segments::F | Segments::G,
-    Segments::C | Segments::F,
-    Segments::A | Segments::C | Segments::D | Segments::E | Segments::G,
-    Segments::A | Segments::C | Segments::D | Segments::F | Segments::G,
-    Segments::B | Segments::C | Segments::D | Segments::F,
-    Segments::A | Segments::B | Segments::D | Segments::F | Segments::G,
-    Segments::A | Segments::B | Segments::D | Segments::E | Segments::F | Segments::G,
-    Segments::A | Segments::C | Segments::F,
-    Segments::A | Segments::B | Segments::C | Segments::D | Segments::E | Segments::F | Segments::G,
-    Segments::A | Segments::B | Segments::C | Segments::D | Segments::F | Segments::G,
-];
+#[derive(Debug)]
+struct Display {
+    /// The wire labels in use; each label's index is its bit in a pattern.
+    labels: Vec<char>,
+    symbols: Vec<u32>,
+}
+
+impl Display {
+    fn new(symbols: &[&str]) -> Result<Self, SolveError> {
+        let labels = symbols
+            .iter()
+            .flat_map(|s| s.chars())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        if labels.len() > u32::BITS as usize {
+            return Err(SolveError::TooManyWires(labels.len()));
+        }
+
+        let mut display = Self {
+            labels,
+            symbols: vec![],
+        };
+        display.symbols = symbols
+            .iter()
+            .map(|s| display.pattern(s))
+            .collect::<Result<_, _>>()?;
+        Ok(display)
+    }
+
+    fn seven_segment() -> Self {
+        Self::new(&[
+            "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+        ])
+        .expect("seven segment display to be valid")
+    }
+
+    fn wires(&self) -> usize {
+        self.labels.len()
+    }
+
+    fn pattern(&self, s: &str) -> Result<u32, SolveError> {
+        s.chars().try_fold(0, |acc, c| {
+            let wire = self
+                .labels
+                .binary_search(&c)
+                .map_err(|_| SolveError::UnknownLabel(c))?;
+            Ok(acc | 1 << wire)
+        })
+    }
+
+    fn solve(&self, observations: &[u32]) -> Result<Wiring, SolveError> {
+        let mut solutions = vec![];
+        self.search(observations, &mut vec![], &mut solutions);
+        match solutions.len() {
+            0 => Err(SolveError::NoSolution),
+            1 => Ok(solutions.remove(0)),
+            _ => Err(SolveError::Ambiguous),
+        }
+    }
+
+    fn search(&self, observations: &[u32], mapping: &mut Vec<usize>, solutions: &mut Vec<Wiring>) {
+        if solutions.len() > 1 || !self.consistent(observations, mapping) {
+            return;
+        }
+        if mapping.len() == self.wires() {
+            solutions.push(Wiring(mapping.clone()));
+            return;
+        }
+        for target in 0..self.wires() {
+            if !mapping.contains(&target) {
+                mapping.push(target);
+                self.search(observations, mapping, solutions);
+                mapping.pop();
+            }
+        }
+    }
+
+    fn consistent(&self, observations: &[u32], mapping: &[usize]) -> bool {
+        let wiring = Wiring(mapping.to_vec());
+        let assigned = ((1u64 << mapping.len()) - 1) as u32;
+        observations.iter().all(|&observed| {
+            let lit = wiring.translate(observed & assigned);
+            let unlit = wiring.translate(!observed & assigned);
+            self.symbols.iter().any(|&symbol| {
+                symbol.count_ones() == observed.count_ones()
+                    && symbol & lit == lit
+                    && symbol & unlit == 0
+            })
+        })
+    }
+
+    fn decode(&self, wiring: &Wiring, observed: u32) -> Option<usize> {
+        let pattern = wiring.translate(observed);
+        self.symbols.iter().position(|&symbol| symbol == pattern)
+    }
+}
 
 #[derive(Debug)]
 struct SegmentSequence<'a> {
@@ -45,32 +138,25 @@ struct SegmentSequence<'a> {
 }
 
 impl<'a> SegmentSequence<'a> {
-    fn decode(&self) -> Vec<u8> {
-        if let Some(decode) = Segments::as_vec().iter().permutations(7).find(|p| {
-            self.patterns
-                .iter()
-                .map(|s| {
-                    s.chars()
-                        .fold(0u8, |acc, c| acc | p[c as usize - 'a' as usize])
-                })
-                .all(|d| DIGITS.contains(&d))
-        }) {
-            self.output
-                .iter()
-                .map(|s| {
-                    s.chars()
-                        .fold(0u8, |acc, c| acc | decode[c as usize - 'a' as usize])
-                })
-                .filter_map(|d| DIGITS.iter().position(|i| i == &d))
-                .map(|d| d as u8)
-                .collect()
-        } else {
-            panic!("No decode found")
+    fn decode(&self, display: &Display) -> Result<Vec<u8>, SolveError> {
+        let patterns = self
+            .patterns
+            .iter()
+            .map(|s| display.pattern(s))
+            .collect::<Result<Vec<_>, _>>()?;
+        let wiring = display.solve(&patterns)?;
+        let mut digits = vec![];
+        for s in &self.output {
+            if let Some(digit) = display.decode(&wiring, display.pattern(s)?) {
+                digits.push(digit as u8);
+            }
         }
+        Ok(digits)
     }
 }
 
 struct SegmentSequences<'a> {
+    display: Display,
     sequences: Vec<SegmentSequence<'a>>,
 }
 
@@ -78,7 +164,7 @@ impl<'a> SegmentSequences<'a> {
     fn count(&self, digits: Vec<u8>) -> usize {
         self.sequences
             .iter()
-            .flat_map(SegmentSequence::decode)
+            .flat_map(|s| s.decode(&self.display).expect("decode sequence"))
             .filter(|d| digits.contains(d))
             .count()
     }
@@ -86,7 +172,7 @@ impl<'a> SegmentSequences<'a> {
     fn sum(&self) -> u32 {
         self.sequences
             .iter()
-            .map(SegmentSequence::decode)
+            .map(|s| s.decode(&self.display).expect("decode sequence"))
             .map(|digits| digits.iter().fold(0u32, |acc, d| acc * 10u32 + *d as u32))
             .sum()
     }
@@ -125,7 +211,10 @@ impl<'a> TryFrom<&'a str> for SegmentSequences<'a> {
             })
             .collect();
 
-        Ok(SegmentSequences { sequences })
+        Ok(SegmentSequences {
+            display: Display::seven_segment(),
+            sequences,
+        })
     }
 }
 
@@ -157,3 +246,40 @@ fn part_1() {
 
     assert_eq!(61229, segment_sequences.sum());
 }
+
+#[test]
+fn test_solve() {
+    let display = Display::new(&["a", "ab", "abc"]).expect("valid display");
+    let pattern = |s| display.pattern(s).expect("known labels");
+    let observations = ["c", "bc", "abc"].map(pattern);
+    let wiring = display.solve(&observations).expect("unique wiring");
+    assert_eq!(Wiring(vec![2, 1, 0]), wiring);
+    assert_eq!(Some(1), display.decode(&wiring, pattern("cb")));
+
+    let display = Display::new(&["a", "b", "ab"]).expect("valid display");
+    let observations = ["a", "b", "ab"].map(|s| display.pattern(s).expect("known labels"));
+    assert_eq!(Err(SolveError::Ambiguous), display.solve(&observations));
+
+    let display = Display::seven_segment();
+    let observations = ["a", "ab", "abc"].map(|s| display.pattern(s).expect("known labels"));
+    assert_eq!(Err(SolveError::NoSolution), display.solve(&observations));
+}
+
+#[test]
+fn test_labels() {
+    let display = Display::new(&["A", "AB", "B?"]).expect("valid display");
+    assert_eq!(3, display.wires());
+    assert_eq!(Ok(0b110), display.pattern("AB"));
+    assert_eq!(Err(SolveError::UnknownLabel('a')), display.pattern("a"));
+
+    let labels = ('A'..='Z').chain('a'..='f').collect::<String>();
+    let display = Display::new(&[&labels]).expect("32 wires");
+    assert_eq!(Ok(u32::MAX), display.pattern(&labels));
+    assert_eq!(Err(SolveError::Ambiguous), display.solve(&[u32::MAX]));
+
+    let labels = ('A'..='Z').chain('a'..='g').collect::<String>();
+    assert!(matches!(
+        Display::new(&[&labels]),
+        Err(SolveError::TooManyWires(33))
+    ));
+}
@@ -4,6 +4,53 @@ use ndarray::{Array2, ShapeError};
 
 const INPUT: &str = include_str!("input");
 
+type Point = (usize, usize);
+
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+enum Connectivity {
+    #[default]
+    Orthogonal,
+    Diagonal,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct BasinRules {
+    wall: u32,
+    connectivity: Connectivity,
+}
+
+impl Default for BasinRules {
+    fn default() -> Self {
+        Self {
+            wall: 9,
+            connectivity: Connectivity::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Basin {
+    size: usize,
+    low_point: (Point, u32),
+    border: Vec<Point>,
+}
+
+#[derive(Debug)]
+struct Basins {
+    #[allow(dead_code)]
+    labels: Array2<Option<usize>>,
+    basins: Vec<Basin>,
+}
+
+impl Basins {
+    fn sizes(&self) -> Vec<usize> {
+        let mut sizes = self.basins.iter().map(|b| b.size).collect::<Vec<_>>();
+        sizes.sort_by(|a, b| b.cmp(a));
+        sizes
+    }
+}
+
 #[derive(Debug)]
 struct LavaTubes {
     map: Array2<u32>,
@@ -31,14 +78,32 @@ impl FromStr for LavaTubes {
 }
 
 impl LavaTubes {
-    fn low_points(&self) -> Vec<((usize, usize), u32)> {
+    fn neighbors(
+        &self,
+        (row, col): Point,
+        connectivity: Connectivity,
+    ) -> impl Iterator<Item = Option<Point>> + '_ {
+        let (rows, cols) = self.map.dim();
+        (-1..=1)
+            .flat_map(|dr| (-1..=1).map(move |dc| (dr, dc)))
+            .filter(move |(dr, dc)| match connectivity {
+                Connectivity::Orthogonal => (*dr == 0) != (*dc == 0),
+                Connectivity::Diagonal => (*dr, *dc) != (0, 0),
+            })
+            .map(move |(dr, dc)| {
+                let row = row.checked_add_signed(dr).filter(|r| *r < rows)?;
+                let col = col.checked_add_signed(dc).filter(|c| *c < cols)?;
+                Some((row, col))
+            })
+    }
+
+    fn low_points(&self) -> Vec<(Point, u32)> {
         self.map
             .indexed_iter()
-            .filter(|((row, col), val)| {
-                self.less_than(row.wrapping_sub(1), *col, val)
-                    && self.less_than(row + 1, *col, val)
-                    && self.less_than(*row, col.wrapping_sub(1), val)
-                    && self.less_than(*row, col + 1, val)
+            .filter(|(point, val)| {
+                self.neighbors(*point, Connectivity::Orthogonal)
+                    .flatten()
+                    .all(|neighbor| **val < self.map[neighbor])
             })
             .map(|(point, val)| (point, *val))
             .collect()
@@ -48,37 +113,57 @@ impl LavaTubes {
         self.low_points().iter().map(|(_, val)| 1 + val).sum()
     }
 
-    fn less_than(&self, row: usize, col: usize, val: &u32) -> bool {
-        self.map.get((row, col)).map_or(true, |v| val < v)
-    }
+    fn basins(&self, rules: BasinRules) -> Basins {
+        let mut labels = Array2::from_elem(self.map.dim(), None);
+        let mut basins = vec![];
 
-    fn basin_sizes(&self) -> Vec<usize> {
-        let mut sizes = self
-            .low_points()
-            .iter()
-            .map(|(point, _)| self.basin_points(point, None).len())
-            .collect::<Vec<usize>>();
-        sizes.sort_by(|a, b| b.cmp(a));
-        sizes
-    }
+        for (start, height) in self.map.indexed_iter() {
+            if *height >= rules.wall || labels[start].is_some() {
+                continue;
+            }
+
+            let label = basins.len();
+            labels[start] = Some(label);
+            let mut stack = vec![start];
+            let mut basin = Basin {
+                size: 0,
+                low_point: (start, *height),
+                border: vec![],
+            };
+
+            while let Some(point) = stack.pop() {
+                basin.size += 1;
+                let height = self.map[point];
+                if (height, point) < (basin.low_point.1, basin.low_point.0) {
+                    basin.low_point = (point, height);
+                }
+
+                let mut on_border = false;
+                for neighbor in self.neighbors(point, rules.connectivity) {
+                    match neighbor {
+                        Some(neighbor) if self.map[neighbor] < rules.wall => {
+                            if labels[neighbor].is_none() {
+                                labels[neighbor] = Some(label);
+                                stack.push(neighbor);
+                            }
+                        }
+                        _ => on_border = true,
+                    }
+                }
+                if on_border {
+                    basin.border.push(point);
+                }
+            }
 
-    fn basin_points(&self, point: &(usize, usize), basin: Option<u32>) -> Vec<(usize, usize)> {
-        match (basin, self.map.get(*point)) {
-            (_, Some(curr)) if *curr == 9 => None,
-            (Some(basin), Some(curr)) if basin < *curr => Some(*curr),
-            (None, Some(curr)) => Some(*curr),
-            _ => None,
+            basin.border.sort();
+            basins.push(basin);
         }
-        .map_or(vec![], |curr| {
-            let mut points = vec![*point];
-            points.append(&mut self.basin_points(&(point.0.wrapping_sub(1), point.1), Some(curr)));
-            points.append(&mut self.basin_points(&(point.0 + 1, point.1), Some(curr)));
-            points.append(&mut self.basin_points(&(point.0, point.1.wrapping_sub(1)), Some(curr)));
-            points.append(&mut self.basin_points(&(point.0, point.1 + 1), Some(curr)));
-            points.sort();
-            points.dedup();
-            points
-        })
+
+        Basins { labels, basins }
+    }
+
+    fn basin_sizes(&self) -> Vec<usize> {
+        self.basins(BasinRules::default()).sizes()
     }
 }
 
@@ -123,3 +208,55 @@ fn part_2() {
             .product()
     )
 }
+
+#[test]
+fn test_basins() {
+    let lava_tubes = TEST_INPUT.parse::<LavaTubes>().expect("input to parse");
+    let basins = lava_tubes.basins(BasinRules::default());
+
+    assert_eq!(
+        vec![((0, 1), 1), ((0, 9), 0), ((2, 2), 5), ((4, 6), 5)],
+        basins
+            .basins
+            .iter()
+            .map(|b| b.low_point)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![3, 9, 14, 9],
+        basins.basins.iter().map(|b| b.size).collect::<Vec<_>>()
+    );
+    assert_eq!(vec![(0, 0), (0, 1), (1, 0)], basins.basins[0].border);
+    assert_eq!(Some(0), basins.labels[(1, 0)]);
+    assert_eq!(None, basins.labels[(0, 2)]);
+    assert_eq!(Some(3), basins.labels[(4, 9)]);
+    assert_eq!(
+        50 - basins.basins.iter().map(|b| b.size).sum::<usize>(),
+        basins.labels.iter().filter(|l| l.is_none()).count()
+    );
+}
+
+#[test]
+fn test_basin_rules() {
+    let lava_tubes = TEST_INPUT.parse::<LavaTubes>().expect("input to parse");
+
+    let lower = lava_tubes.basins(BasinRules {
+        wall: 8,
+        ..Default::default()
+    });
+    assert_eq!(vec![9, 7, 6, 3], lower.sizes());
+
+    let diagonal = lava_tubes.basins(BasinRules {
+        connectivity: Connectivity::Diagonal,
+        ..Default::default()
+    });
+    assert_eq!(vec![35], diagonal.sizes());
+    assert_eq!(((0, 9), 0), diagonal.basins[0].low_point);
+
+    let open = lava_tubes.basins(BasinRules {
+        wall: 10,
+        ..Default::default()
+    });
+    assert_eq!(vec![50], open.sizes());
+    assert_eq!(26, open.basins[0].border.len());
+}
@@ -1,3 +1,5 @@
+use sliding_windows::SlidingWindows;
+
 const INPUT: &str = include_str!("input");
 
 fn main() {
@@ -8,26 +10,7 @@ fn main() {
 }
 
 fn find_generic_marker(input: &str, size: usize) -> Option<usize> {
-    let chars = input.chars().collect::<Vec<_>>();
-    let mut iter = chars.windows(size).enumerate();
-
-    while let Some((index, window)) = iter.next() {
-        if (0..window.len() - 1).into_iter().rev().all(|i| {
-            (i + 1..window.len()).all(|j| {
-                let result = window[i] != window[j];
-                if !result {
-                    for _ in 0..i {
-                        iter.next();
-                    }
-                }
-                result
-            })
-        }) {
-            return Some(index + size);
-        }
-    }
-
-    None
+    input.chars().distinct_windows(size).next()
 }
 
 fn input_to_first_marker(input: &str) -> Option<usize> {
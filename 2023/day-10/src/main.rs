@@ -1,6 +1,6 @@
 use std::{
-    cmp::{max, min},
-    collections::HashMap,
+    cmp::max,
+    collections::{HashMap, HashSet},
 };
 
 use nom::{
@@ -84,7 +84,7 @@ impl Element {
 #[derive(Debug)]
 enum ParsedElement {
     Pipe(Element),
-    Ground,
+    Ground(Position),
     Start(Position),
 }
 
@@ -120,9 +120,73 @@ impl<'a> Iterator for MapWalker<'a> {
     }
 }
 
+/// Draws a pipe from its connections, heavy for the loop and light otherwise.
+fn pipe_symbol(connections: &[Direction], heavy: bool) -> char {
+    let has = |d: Direction| connections.contains(&d);
+    let (light, bold) = match (
+        has(Direction::Up),
+        has(Direction::Down),
+        has(Direction::Left),
+        has(Direction::Right),
+    ) {
+        (true, true, _, _) => ('│', '┃'),
+        (_, _, true, true) => ('─', '━'),
+        (true, _, _, true) => ('└', '┗'),
+        (true, _, true, _) => ('┘', '┛'),
+        (_, true, true, _) => ('┐', '┓'),
+        (_, true, _, true) => ('┌', '┏'),
+        _ => ('?', '?'),
+    };
+    if heavy {
+        bold
+    } else {
+        light
+    }
+}
+
+/// The main loop through `S`, with its tiles in walking order starting at `S`.
+#[derive(Debug)]
+struct PipeLoop {
+    cells: Vec<Position>,
+    start_connections: Vec<Direction>,
+    enclosed: HashSet<Position>,
+}
+
+impl PipeLoop {
+    fn start_tile(&self) -> char {
+        match pipe_symbol(&self.start_connections, false) {
+            '│' => '|',
+            '─' => '-',
+            '└' => 'L',
+            '┘' => 'J',
+            '┐' => '7',
+            '┌' => 'F',
+            _ => '?',
+        }
+    }
+
+    /// Twice the area enclosed by the loop's centre line, by the shoelace formula.
+    fn double_area(&self) -> usize {
+        self.cells
+            .iter()
+            .zip(self.cells.iter().cycle().skip(1))
+            .map(|(a, b)| (a.x * b.y) as isize - (b.x * a.y) as isize)
+            .sum::<isize>()
+            .unsigned_abs()
+    }
+
+    /// Interior tiles from Pick's theorem: `A = i + b / 2 - 1`.
+    fn interior_count(&self) -> usize {
+        (self.double_area() + 2 - self.cells.len()) / 2
+    }
+}
+
 struct Map {
     start: Position,
     grid: HashMap<Position, Element>,
+    ground: HashSet<Position>,
+    width: usize,
+    height: usize,
 }
 
 impl<'a> Map {
@@ -132,23 +196,37 @@ impl<'a> Map {
             .find_map(|e| match e {
                 ParsedElement::Start(p) => Some(p.clone()),
                 ParsedElement::Pipe(_) => None,
-                ParsedElement::Ground => None,
+                ParsedElement::Ground(_) => None,
             })
             .expect("to find a start position");
 
-        let grid = elements
-            .into_iter()
-            .filter_map(|e| match e {
-                ParsedElement::Pipe(e) => Some(e),
-                ParsedElement::Ground => None,
-                ParsedElement::Start(_) => None,
-            })
-            .fold(HashMap::new(), |mut acc, e| {
-                acc.insert(e.position.clone(), e);
-                acc
-            });
+        let mut grid = HashMap::new();
+        let mut ground = HashSet::new();
+        let (mut width, mut height) = (0, 0);
+        for element in elements {
+            let position = match element {
+                ParsedElement::Pipe(e) => {
+                    let position = e.position.clone();
+                    grid.insert(position.clone(), e);
+                    position
+                }
+                ParsedElement::Ground(p) => {
+                    ground.insert(p.clone());
+                    p
+                }
+                ParsedElement::Start(p) => p,
+            };
+            width = max(width, position.x);
+            height = max(height, position.y);
+        }
 
-        Self { start, grid }
+        Self {
+            start,
+            grid,
+            ground,
+            width,
+            height,
+        }
     }
 
     fn get(&self, position: &Position, direction: &Direction) -> Option<&Element> {
@@ -182,6 +260,76 @@ impl<'a> Map {
             iters.pop().expect("at least two path iterators"),
         )
     }
+
+    fn pipe_loop(&self) -> PipeLoop {
+        let (forward, _) = self.paths();
+        let cells = Some(self.start.clone())
+            .into_iter()
+            .chain(
+                forward
+                    .map(|e| e.position.clone())
+                    .take_while(|p| *p != self.start),
+            )
+            .collect::<Vec<_>>();
+
+        let start_connections = self.start_connections();
+        let path = cells.iter().collect::<HashSet<_>>();
+        let crosses = |position: &Position| {
+            if *position == self.start {
+                start_connections.contains(&Direction::Up)
+            } else {
+                self.grid[position].connections.contains(&Direction::Up)
+            }
+        };
+
+        let mut enclosed = HashSet::new();
+        for y in 1..=self.height {
+            let mut inside = false;
+            for x in 1..=self.width {
+                let position = Position { x, y };
+                if path.contains(&position) {
+                    inside ^= crosses(&position);
+                } else if inside {
+                    enclosed.insert(position);
+                }
+            }
+        }
+
+        let pipe_loop = PipeLoop {
+            cells,
+            start_connections,
+            enclosed,
+        };
+        debug_assert_eq!(
+            pipe_loop.interior_count(),
+            pipe_loop.enclosed.len(),
+            "row scan to agree with Pick's theorem"
+        );
+        pipe_loop
+    }
+
+    fn render(&self, pipe_loop: &PipeLoop) -> String {
+        let path = pipe_loop.cells.iter().collect::<HashSet<_>>();
+        let mut rendered = String::new();
+        for y in 1..=self.height {
+            for x in 1..=self.width {
+                let position = Position { x, y };
+                rendered.push(if pipe_loop.enclosed.contains(&position) {
+                    '▒'
+                } else if position == self.start {
+                    pipe_symbol(&pipe_loop.start_connections, true)
+                } else if let Some(element) = self.grid.get(&position) {
+                    pipe_symbol(&element.connections, path.contains(&position))
+                } else if self.ground.contains(&position) {
+                    '.'
+                } else {
+                    ' '
+                });
+            }
+            rendered.push('\n');
+        }
+        rendered
+    }
 }
 
 type Span<'a> = LocatedSpan<&'a str>;
@@ -191,7 +339,9 @@ fn parse_element(input: Span) -> IResult<Span, ParsedElement> {
     let x = start.get_column();
     let y = start.location_line() as usize;
     alt((
-        map(complete::char('.'), |_| ParsedElement::Ground),
+        map(complete::char('.'), move |_| {
+            ParsedElement::Ground(Position { x, y })
+        }),
         map(complete::char('S'), move |_| {
             ParsedElement::Start(Position { x, y })
         }),
@@ -244,59 +394,21 @@ fn process_1(input: &str) -> usize {
     reverse.zip(forward).take_while(|(f, r)| f != r).count() + 1
 }
 
-enum Trace {
-    In,
-    Out,
-}
-
 fn process_2(input: &str) -> u32 {
     let map = parse(Span::new(input)).expect("map to parse").1;
-    let (forward, _) = map.paths();
-
-    let mut min_y = usize::MAX;
-    let mut min_x = usize::MAX;
-
-    let mut max_y = usize::MIN;
-    let mut max_x = usize::MIN;
-
-    let mut path =
-        forward
-            .take_while(|f| f.position != map.start)
-            .fold(HashMap::new(), |mut acc, f| {
-                min_y = min(min_y, f.position.y);
-                min_x = min(min_x, f.position.x);
-                max_y = max(max_y, f.position.y);
-                max_x = max(max_x, f.position.x);
-                acc.insert(f.position.clone(), f.connections.clone());
-                acc
-            });
-    path.insert(map.start.clone(), map.start_connections());
-
-    let mut count = 0;
-    for y in min_y..=max_y {
-        let mut trace = Trace::Out;
-        for x in min_x..=max_x {
-            let position = Position { x, y };
-            match (&trace, path.get(&position)) {
-                (Trace::In, None) => {
-                    count += 1;
-                }
-                (Trace::In, Some(directions)) if directions[0] == Direction::Up => {
-                    trace = Trace::Out;
-                }
-                (Trace::Out, Some(directions)) if directions[0] == Direction::Up => {
-                    trace = Trace::In;
-                }
-                _ => {}
-            }
-        }
-    }
-    count
+    map.pipe_loop().enclosed.len() as u32
 }
 
 fn main() {
     println!("part 1: {}", process_1(INPUT));
     println!("part 2: {}", process_2(INPUT));
+
+    let map = parse(Span::new(INPUT)).expect("map to parse").1;
+    let pipe_loop = map.pipe_loop();
+    println!("start tile: {}", pipe_loop.start_tile());
+    if std::env::args().any(|arg| arg == "--render") {
+        print!("{}", map.render(&pipe_loop));
+    }
 }
 
 #[test]
@@ -396,3 +508,71 @@ L7JLJL-JLJLJL--JLJ.L
 ";
     assert_eq!(10, process_2(INPUT))
 }
+
+#[test]
+fn test_pipe_loop() {
+    const INPUT: &str = "..F7.
+.FJ|.
+SJ.L7
+|F--J
+LJ...
+
+";
+    let map = parse(Span::new(INPUT)).expect("map to parse").1;
+    let pipe_loop = map.pipe_loop();
+    assert_eq!(16, pipe_loop.cells.len());
+    assert_eq!(Position { x: 1, y: 3 }, pipe_loop.cells[0]);
+    assert_eq!('F', pipe_loop.start_tile());
+    assert_eq!(16, pipe_loop.double_area());
+    assert_eq!(1, pipe_loop.interior_count());
+    assert_eq!(HashSet::from([Position { x: 3, y: 3 }]), pipe_loop.enclosed);
+}
+
+#[test]
+fn test_render() {
+    const INPUT: &str = "-L|F7
+7S-7|
+L|7||
+-L-J|
+L|-JF
+
+";
+    let map = parse(Span::new(INPUT)).expect("map to parse").1;
+    let pipe_loop = map.pipe_loop();
+    assert_eq!('F', pipe_loop.start_tile());
+    assert_eq!(
+        "─└│┌┐
+┐┏━┓│
+└┃▒┃│
+─┗━┛│
+└│─┘┌
+",
+        map.render(&pipe_loop)
+    );
+
+    const GROUND: &str = "...........
+.S-------7.
+.|F-----7|.
+.||.....||.
+.||.....||.
+.|L-7.F-J|.
+.|..|.|..|.
+.L--J.L--J.
+...........
+
+";
+    let map = parse(Span::new(GROUND)).expect("map to parse").1;
+    assert_eq!(
+        "...........
+.┏━━━━━━━┓.
+.┃┏━━━━━┓┃.
+.┃┃.....┃┃.
+.┃┃.....┃┃.
+.┃┗━┓.┏━┛┃.
+.┃▒▒┃.┃▒▒┃.
+.┗━━┛.┗━━┛.
+...........
+",
+        map.render(&map.pipe_loop())
+    );
+}
@@ -1,7 +1,3 @@
-use std::collections::HashSet;
-
-use itertools::Itertools;
-
 const INPUT: &str = include_str!("input");
 
 #[derive(Debug)]
@@ -16,51 +12,78 @@ impl Galaxy {
     }
 }
 
+/// Extra rows or columns added for every empty one, per axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Expansion {
+    x: isize,
+    y: isize,
+}
+
+impl Expansion {
+    fn uniform(extra: isize) -> Self {
+        Self { x: extra, y: extra }
+    }
+}
+
 struct Image {
     galaxies: Vec<Galaxy>,
-    empty_x: Vec<isize>,
-    empty_y: Vec<isize>,
 }
 
 impl Image {
-    fn new(galaxies: Vec<Galaxy>, multiplier: isize) -> Self {
-        let present_x: HashSet<isize> = galaxies.iter().map(|g| g.x).collect();
-        let empty_x: Vec<isize> = (*present_x.iter().min().expect("min")
-            ..*present_x.iter().max().expect("max"))
-            .filter(|x| !present_x.contains(x))
-            .collect();
-
-        let present_y: HashSet<isize> = galaxies.iter().map(|g| g.y).collect();
-        let empty_y: Vec<isize> = (*present_y.iter().min().expect("min")
-            ..*present_y.iter().max().expect("max"))
-            .filter(|y| !present_y.contains(y))
-            .collect();
+    fn new(galaxies: Vec<Galaxy>, expansion: Expansion) -> Self {
+        let expand_x = Self::expander(galaxies.iter().map(|g| g.x), expansion.x);
+        let expand_y = Self::expander(galaxies.iter().map(|g| g.y), expansion.y);
 
         let galaxies = galaxies
             .iter()
             .map(|g| Galaxy {
-                x: g.x + multiplier * empty_x.iter().filter(|x| **x < g.x).count() as isize,
-                y: g.y + multiplier * empty_y.iter().filter(|y| **y < g.y).count() as isize,
+                x: expand_x(g.x),
+                y: expand_y(g.y),
             })
             .collect();
 
-        Self {
-            galaxies,
-            empty_x,
-            empty_y,
+        Self { galaxies }
+    }
+
+    /// Maps a coordinate to its expanded position, counting the empty lines
+    /// before it from its rank among the occupied ones.
+    fn expander(positions: impl Iterator<Item = isize>, extra: isize) -> impl Fn(isize) -> isize {
+        let mut present = positions.collect::<Vec<_>>();
+        present.sort_unstable();
+        present.dedup();
+        let min = present.first().copied().unwrap_or_default();
+
+        move |position| {
+            let occupied = present.partition_point(|p| *p < position) as isize;
+            position + extra * (position - min - occupied)
         }
     }
 
-    fn distances(&self) -> Vec<isize> {
-        self.galaxies
+    #[allow(dead_code)]
+    fn distance(&self, a: usize, b: usize) -> Option<isize> {
+        Some(self.galaxies.get(a)?.distance(self.galaxies.get(b)?))
+    }
+
+    fn axis_sum(mut positions: Vec<isize>) -> isize {
+        positions.sort_unstable();
+        positions
             .iter()
-            .combinations(2)
-            .map(|g| g[0].distance(&g[1]))
-            .collect()
+            .zip(0..)
+            .scan(0, |prefix, (position, index)| {
+                let sum = position * index - *prefix;
+                *prefix += position;
+                Some(sum)
+            })
+            .sum()
+    }
+
+    fn distance_sum(&self) -> isize {
+        Self::axis_sum(self.galaxies.iter().map(|g| g.x).collect())
+            + Self::axis_sum(self.galaxies.iter().map(|g| g.y).collect())
     }
 }
 
-fn parse_input(input: &str, multiplier: isize) -> Image {
+fn parse_input(input: &str, expansion: Expansion) -> Image {
     let mut galaxies = Vec::new();
     for (y, line) in input.lines().enumerate() {
         for (x, c) in line.chars().enumerate() {
@@ -72,17 +95,17 @@ fn parse_input(input: &str, multiplier: isize) -> Image {
             }
         }
     }
-    Image::new(galaxies, multiplier)
+    Image::new(galaxies, expansion)
 }
 
 fn process_1(input: &str) -> isize {
-    let image = parse_input(input, 1);
-    image.distances().iter().sum()
+    let image = parse_input(input, Expansion::uniform(1));
+    image.distance_sum()
 }
 
 fn process_2(input: &str) -> isize {
-    let image = parse_input(input, 999999);
-    image.distances().iter().sum()
+    let image = parse_input(input, Expansion::uniform(999999));
+    image.distance_sum()
 }
 
 fn main() {
@@ -122,9 +145,55 @@ fn test_process_2() {
 
 ";
 
-    let image = parse_input(INPUT, 9);
-    assert_eq!(1030isize, image.distances().iter().sum());
+    let image = parse_input(INPUT, Expansion::uniform(9));
+    assert_eq!(1030, image.distance_sum());
+
+    let image = parse_input(INPUT, Expansion::uniform(99));
+    assert_eq!(8410, image.distance_sum());
+}
+
+#[cfg(test)]
+const TEST_INPUT: &str = "...#......
+.......#..
+#.........
+..........
+......#...
+.#........
+.........#
+..........
+.......#..
+#...#.....
+";
+
+#[test]
+fn test_distance() {
+    let image = parse_input(TEST_INPUT, Expansion::uniform(1));
+    assert_eq!(Some(9), image.distance(4, 8));
+    assert_eq!(Some(15), image.distance(0, 6));
+    assert_eq!(Some(17), image.distance(2, 5));
+    assert_eq!(Some(5), image.distance(7, 8));
+    assert_eq!(Some(0), image.distance(3, 3));
+    assert_eq!(None, image.distance(0, 9));
+}
+
+#[test]
+fn test_axis_expansion() {
+    for expansion in [
+        Expansion { x: 0, y: 0 },
+        Expansion { x: 1, y: 9 },
+        Expansion { x: 99, y: 0 },
+    ] {
+        let image = parse_input(TEST_INPUT, expansion);
+        let len = image.galaxies.len();
+        let pairwise = (0..len)
+            .flat_map(|a| (a + 1..len).map(move |b| (a, b)))
+            .map(|(a, b)| image.distance(a, b).expect("galaxies"))
+            .sum::<isize>();
+        assert_eq!(pairwise, image.distance_sum());
+    }
 
-    let image = parse_input(INPUT, 99);
-    assert_eq!(8410isize, image.distances().iter().sum());
+    let image = parse_input(TEST_INPUT, Expansion { x: 0, y: 10 });
+    assert_eq!(Some(5), image.distance(0, 1));
+    let image = parse_input(TEST_INPUT, Expansion { x: 10, y: 0 });
+    assert_eq!(Some(15), image.distance(0, 1));
 }
@@ -1,11 +1,157 @@
+use std::{
+    collections::HashMap,
+    fmt::{Display, Write},
+};
+
+use nom::{
+    branch::alt,
+    character::complete::{self, line_ending},
+    combinator::map,
+    multi::{many1, separated_list1},
+    IResult,
+};
+
 const INPUT: &str = include_str!("input");
 
-fn process_1(input: &str) -> u32 {
-    todo!()
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Space {
+    Round,
+    Cube,
+    Empty,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Direction {
+    North,
+    West,
+    South,
+    East,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Platform {
+    rows: Vec<Vec<Space>>,
+}
+
+impl Platform {
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn width(&self) -> usize {
+        self.rows.first().map_or(0, Vec::len)
+    }
+
+    /// Positions of each line the rocks roll along, starting from the edge
+    /// they roll towards.
+    fn lanes(&self, direction: Direction) -> Vec<Vec<(usize, usize)>> {
+        let (height, width) = (self.height(), self.width());
+        match direction {
+            Direction::North => (0..width)
+                .map(|col| (0..height).map(|row| (row, col)).collect())
+                .collect(),
+            Direction::South => (0..width)
+                .map(|col| (0..height).rev().map(|row| (row, col)).collect())
+                .collect(),
+            Direction::West => (0..height)
+                .map(|row| (0..width).map(|col| (row, col)).collect())
+                .collect(),
+            Direction::East => (0..height)
+                .map(|row| (0..width).rev().map(|col| (row, col)).collect())
+                .collect(),
+        }
+    }
+
+    fn tilt(&mut self, direction: Direction) {
+        for lane in self.lanes(direction) {
+            let mut free = 0;
+            for (index, &(row, col)) in lane.iter().enumerate() {
+                match self.rows[row][col] {
+                    Space::Cube => free = index + 1,
+                    Space::Round => {
+                        let (to_row, to_col) = lane[free];
+                        self.rows[row][col] = Space::Empty;
+                        self.rows[to_row][to_col] = Space::Round;
+                        free += 1;
+                    }
+                    Space::Empty => {}
+                }
+            }
+        }
+    }
+
+    fn spin(&mut self) {
+        for direction in [
+            Direction::North,
+            Direction::West,
+            Direction::South,
+            Direction::East,
+        ] {
+            self.tilt(direction);
+        }
+    }
+
+    fn spin_cycles(&mut self, cycles: usize) {
+        let mut seen = HashMap::new();
+        for cycle in 0..cycles {
+            if let Some(start) = seen.insert(self.clone(), cycle) {
+                let remaining = (cycles - cycle) % (cycle - start);
+                (0..remaining).for_each(|_| self.spin());
+                return;
+            }
+            self.spin();
+        }
+    }
+
+    fn north_load(&self) -> usize {
+        self.rows
+            .iter()
+            .zip((1..=self.height()).rev())
+            .map(|(row, load)| row.iter().filter(|s| **s == Space::Round).count() * load)
+            .sum()
+    }
+}
+
+impl Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in &self.rows {
+            for space in row {
+                match space {
+                    Space::Round => f.write_char('O'),
+                    Space::Cube => f.write_char('#'),
+                    Space::Empty => f.write_char('.'),
+                }?;
+            }
+            f.write_char('\n')?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_space(input: &str) -> IResult<&str, Space> {
+    alt((
+        map(complete::char('O'), |_| Space::Round),
+        map(complete::char('#'), |_| Space::Cube),
+        map(complete::char('.'), |_| Space::Empty),
+    ))(input)
+}
+
+fn parse(input: &str) -> IResult<&str, Platform> {
+    map(separated_list1(line_ending, many1(parse_space)), |rows| {
+        Platform { rows }
+    })(input)
+}
+
+fn process_1(input: &str) -> usize {
+    let (_input, mut platform) = parse(input).expect("platform to parse");
+    platform.tilt(Direction::North);
+    platform.north_load()
 }
 
-fn process_2(input: &str) -> u32 {
-    todo!()
+fn process_2(input: &str) -> usize {
+    let (_input, mut platform) = parse(input).expect("platform to parse");
+    platform.spin_cycles(1_000_000_000);
+    platform.north_load()
 }
 
 fn main() {
@@ -13,18 +159,104 @@ fn main() {
     println!("part 2: {}", process_2(INPUT));
 }
 
+#[cfg(test)]
+const TEST_INPUT: &str = "O....#....
+O.OO#....#
+.....##...
+OO.#O....O
+.O.....O#.
+O.#..O.#.#
+..O..#O..O
+.......O..
+#....###..
+#OO..#....
+";
+
 #[test]
 fn test_process_1() {
-    const INPUT: &str = "
-
-";
-    assert_eq!(0, process_1(INPUT))
+    assert_eq!(136, process_1(TEST_INPUT))
 }
 
 #[test]
 fn test_process_2() {
-    const INPUT: &str = "
+    assert_eq!(64, process_2(TEST_INPUT))
+}
 
-";
-    assert_eq!(0, process_2(INPUT))
+#[test]
+fn test_tilt() {
+    let (_input, mut platform) = parse(TEST_INPUT).expect("platform to parse");
+    platform.tilt(Direction::North);
+    assert_eq!(
+        "OOOO.#.O..
+OO..#....#
+OO..O##..O
+O..#.OO...
+........#.
+..#....#.#
+..O..#.O.O
+..O.......
+#....###..
+#....#....
+",
+        platform.to_string()
+    );
+
+    let (_input, mut platform) = parse("O.#.O\n..O..\n#...O").expect("platform to parse");
+    platform.tilt(Direction::East);
+    assert_eq!(".O#.O\n....O\n#...O\n", platform.to_string());
+    platform.tilt(Direction::South);
+    assert_eq!("..#.O\n....O\n#O..O\n", platform.to_string());
+    platform.tilt(Direction::West);
+    assert_eq!("..#O.\nO....\n#OO..\n", platform.to_string());
+}
+
+#[test]
+fn test_spin_cycles() {
+    let (_input, platform) = parse(TEST_INPUT).expect("platform to parse");
+    let expected = [
+        ".....#....
+....#...O#
+...OO##...
+.OO#......
+.....OOO#.
+.O#...O#.#
+....O#....
+......OOOO
+#...O###..
+#..OO#....
+",
+        ".....#....
+....#...O#
+.....##...
+..O#......
+.....OOO#.
+.O#...O#.#
+....O#...O
+.......OOO
+#..OO###..
+#.OOO#...O
+",
+        ".....#....
+....#...O#
+.....##...
+..O#......
+.....OOO#.
+.O#...O#.#
+....O#...O
+.......OOO
+#...O###.O
+#.OOO#...O
+",
+    ];
+    for (cycles, expected) in (1..).zip(expected) {
+        let mut spun = platform.clone();
+        spun.spin_cycles(cycles);
+        assert_eq!(expected, spun.to_string());
+    }
+
+    let mut direct = platform.clone();
+    (0..100).for_each(|_| direct.spin());
+    let mut detected = platform.clone();
+    detected.spin_cycles(100);
+    assert_eq!(direct, detected);
 }
@@ -1,37 +1,159 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    ops::Range,
+};
+
 use nom::{
     bytes::complete::tag,
     character::complete::{self, alpha1, line_ending, space1},
+    combinator::verify,
     multi::{many1, separated_list1},
     sequence::{delimited, separated_pair, terminated},
     IResult,
 };
+use thiserror::Error;
 
 const INPUT: &str = include_str!("input");
 
+/// Values are `u64`s, held as `u128` so that ranges can end past `u64::MAX`.
+const DOMAIN_END: u128 = u64::MAX as u128 + 1;
+
 struct Almanac {
-    seeds: Vec<u64>,
+    seeds: Vec<Range<u128>>,
     conversions: Vec<Conversion>,
 }
 
+#[derive(Debug, Error, PartialEq)]
+enum AlmanacError {
+    #[error("no conversions lead from '{from}' to '{to}'")]
+    Missing { from: String, to: String },
+    #[error("conversions loop back to '{0}'")]
+    Cycle(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Direction {
+    Forward,
+    Inverse,
+}
+
 impl Almanac {
-    fn find_conversion(&self, from: &str) -> Option<&Conversion> {
-        self.conversions.iter().find(|c| c.from == from)
+    fn check_acyclic(&self) -> Result<(), AlmanacError> {
+        fn visit<'a>(
+            almanac: &'a Almanac,
+            category: &'a str,
+            stack: &mut Vec<&'a str>,
+            done: &mut HashSet<&'a str>,
+        ) -> Result<(), AlmanacError> {
+            if stack.contains(&category) {
+                return Err(AlmanacError::Cycle(category.to_string()));
+            }
+            if !done.insert(category) {
+                return Ok(());
+            }
+            stack.push(category);
+            for conversion in almanac.conversions.iter().filter(|c| c.from == category) {
+                visit(almanac, &conversion.to, stack, done)?;
+            }
+            stack.pop();
+            Ok(())
+        }
+
+        let mut done = HashSet::new();
+        self.conversions
+            .iter()
+            .try_for_each(|c| visit(self, &c.from, &mut vec![], &mut done))
     }
 
-    fn lowest_location(&self) -> u64 {
-        let mut source = "seed";
-        let mut seeds = self.seeds.clone();
+    /// Finds the shortest chain of conversions between two categories,
+    /// walking conversions backwards as well when `inverse` is allowed.
+    fn path(
+        &self,
+        from: &str,
+        to: &str,
+        inverse: bool,
+    ) -> Result<Vec<(&Conversion, Direction)>, AlmanacError> {
+        self.check_acyclic()?;
+
+        let mut previous: HashMap<&str, (&Conversion, Direction)> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                let mut path = vec![];
+                let mut category = to;
+                while category != from {
+                    let (conversion, direction) = previous[category];
+                    path.push((conversion, direction));
+                    category = match direction {
+                        Direction::Forward => &conversion.from,
+                        Direction::Inverse => &conversion.to,
+                    };
+                }
+                path.reverse();
+                return Ok(path);
+            }
 
-        while source != "location" {
-            let conversion = self
-                .find_conversion(source)
-                .expect(&format!("conversion '{}' to exist", source));
-            source = &conversion.to;
+            for conversion in &self.conversions {
+                let next = if conversion.from == category {
+                    Some((conversion.to.as_str(), Direction::Forward))
+                } else if inverse && conversion.to == category {
+                    Some((conversion.from.as_str(), Direction::Inverse))
+                } else {
+                    None
+                };
+                if let Some((next, direction)) = next {
+                    if next != from && !previous.contains_key(next) {
+                        previous.insert(next, (conversion, direction));
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
 
-            conversion.convert(&mut seeds);
+        Err(AlmanacError::Missing {
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+    }
+
+    fn chain(&self, from: &str, to: &str) -> Result<PiecewiseMap, AlmanacError> {
+        Ok(self
+            .path(from, to, false)?
+            .into_iter()
+            .fold(PiecewiseMap::identity(), |map, (conversion, _)| {
+                map.then(&conversion.into())
+            }))
+    }
+
+    /// Every value in `to` that `value` in `from` corresponds to.
+    #[allow(dead_code)]
+    fn lookup(&self, from: &str, to: &str, value: u128) -> Result<Vec<u128>, AlmanacError> {
+        let mut values = vec![value];
+        for (conversion, direction) in self.path(from, to, true)? {
+            let map = PiecewiseMap::from(conversion);
+            values = match direction {
+                Direction::Forward => values.iter().map(|v| map.get(*v)).collect(),
+                Direction::Inverse => values.iter().flat_map(|v| map.preimages(*v)).collect(),
+            };
+            values.sort_unstable();
+            values.dedup();
         }
+        Ok(values)
+    }
+
+    #[allow(dead_code)]
+    fn seeds_for_location(&self, location: u128) -> Result<Vec<u128>, AlmanacError> {
+        self.lookup("location", "seed", location)
+    }
 
-        *seeds.iter().min().expect("find minimum seed")
+    fn lowest_location(&self) -> u128 {
+        self.chain("seed", "location")
+            .expect("seeds to convert to locations")
+            .map_ranges(&self.seeds)
+            .iter()
+            .map(|range| range.start)
+            .min()
+            .expect("find minimum seed")
     }
 }
 
@@ -42,49 +164,180 @@ struct Conversion {
     transformations: Vec<Transformation>,
 }
 
-impl Conversion {
-    fn convert(&self, seeds: &mut Vec<u64>) {
-        for seed in seeds.iter_mut() {
-            if let Some(transformation) = self.transformations.iter().find(|transformation| {
-                transformation.source_start <= *seed
-                    && *seed < transformation.source_start + transformation.length as u64
-            }) {
-                *seed = transformation.dest_start + (*seed - transformation.source_start);
+#[derive(Debug)]
+struct Transformation {
+    source_start: u128,
+    dest_start: u128,
+    length: u128,
+}
+
+impl Transformation {
+    fn in_domain(&self) -> bool {
+        self.source_start + self.length <= DOMAIN_END && self.dest_start + self.length <= DOMAIN_END
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Segment {
+    source: Range<u128>,
+    dest: u128,
+}
+
+impl Segment {
+    fn dest_range(&self) -> Range<u128> {
+        self.dest..self.dest + (self.source.end - self.source.start)
+    }
+}
+
+/// Sorted segments covering the whole `u64` domain, each shifted to its
+/// destination.
+#[derive(Clone, Debug, PartialEq)]
+struct PiecewiseMap {
+    segments: Vec<Segment>,
+}
+
+impl PiecewiseMap {
+    fn identity() -> Self {
+        Self {
+            segments: vec![Segment {
+                source: 0..DOMAIN_END,
+                dest: 0,
+            }],
+        }
+    }
+
+    fn get(&self, value: u128) -> u128 {
+        self.split(value..value + 1)
+            .next()
+            .map_or(value, |segment| segment.dest)
+    }
+
+    fn preimages(&self, value: u128) -> Vec<u128> {
+        self.segments
+            .iter()
+            .filter(|s| s.dest_range().contains(&value))
+            .map(|s| s.source.start + (value - s.dest))
+            .collect()
+    }
+
+    /// The segments restricted to `range`, in source order.
+    fn split(&self, range: Range<u128>) -> impl Iterator<Item = Segment> + '_ {
+        let first = self
+            .segments
+            .partition_point(|s| s.source.end <= range.start);
+        self.segments[first..]
+            .iter()
+            .take_while(move |s| s.source.start < range.end)
+            .map(move |s| {
+                let start = s.source.start.max(range.start);
+                let end = s.source.end.min(range.end);
+                Segment {
+                    source: start..end,
+                    dest: s.dest + (start - s.source.start),
+                }
+            })
+            .filter(|segment| !segment.source.is_empty())
+    }
+
+    fn map_ranges(&self, ranges: &[Range<u128>]) -> Vec<Range<u128>> {
+        ranges
+            .iter()
+            .flat_map(|range| self.split(range.clone()).map(|s| s.dest_range()))
+            .collect()
+    }
+
+    /// Applies `self` and then `next`, merging segments that stay contiguous.
+    fn then(&self, next: &PiecewiseMap) -> Self {
+        let mut segments: Vec<Segment> = vec![];
+        for segment in &self.segments {
+            for piece in next.split(segment.dest_range()) {
+                let start = segment.source.start + (piece.source.start - segment.dest);
+                let source = start..start + (piece.source.end - piece.source.start);
+                match segments.last_mut() {
+                    Some(last)
+                        if last.source.end == source.start
+                            && last.dest_range().end == piece.dest =>
+                    {
+                        last.source.end = source.end;
+                    }
+                    _ => segments.push(Segment {
+                        source,
+                        dest: piece.dest,
+                    }),
+                }
             }
         }
+        Self { segments }
     }
 }
 
-#[derive(Debug)]
-struct Transformation {
-    source_start: u64,
-    dest_start: u64,
-    length: u64,
+impl From<&Conversion> for PiecewiseMap {
+    fn from(conversion: &Conversion) -> Self {
+        let mut transformations = conversion.transformations.iter().collect::<Vec<_>>();
+        transformations.sort_by_key(|t| t.source_start);
+
+        let mut segments = vec![];
+        let mut cursor = 0;
+        for transformation in transformations {
+            let start = transformation.source_start.max(cursor);
+            let end = transformation.source_start + transformation.length;
+            if start >= end {
+                continue;
+            }
+            if cursor < start {
+                segments.push(Segment {
+                    source: cursor..start,
+                    dest: cursor,
+                });
+            }
+            segments.push(Segment {
+                source: start..end,
+                dest: transformation.dest_start + (start - transformation.source_start),
+            });
+            cursor = end;
+        }
+        if cursor < DOMAIN_END {
+            segments.push(Segment {
+                source: cursor..DOMAIN_END,
+                dest: cursor,
+            });
+        }
+
+        Self { segments }
+    }
 }
 
-fn parse_seeds(input: &str) -> IResult<&str, Vec<u64>> {
+fn parse_seeds(input: &str) -> IResult<&str, Vec<Range<u128>>> {
     let (input, seeds) = delimited(
         tag("seeds: "),
         separated_list1(space1, complete::u64),
         line_ending,
     )(input)?;
+    let seeds = seeds
+        .iter()
+        .map(|seed| u128::from(*seed)..u128::from(*seed) + 1)
+        .collect();
     Ok((input, seeds))
 }
 
-fn parse_transformation(input: &str) -> IResult<&str, Transformation> {
+fn parse_transformation_fields(input: &str) -> IResult<&str, Transformation> {
     let (input, dest_start) = terminated(complete::u64, space1)(input)?;
     let (input, source_start) = terminated(complete::u64, space1)(input)?;
     let (input, length) = terminated(complete::u64, line_ending)(input)?;
     Ok((
         input,
         Transformation {
-            source_start,
-            dest_start,
-            length,
+            source_start: source_start.into(),
+            dest_start: dest_start.into(),
+            length: length.into(),
         },
     ))
 }
 
+fn parse_transformation(input: &str) -> IResult<&str, Transformation> {
+    verify(parse_transformation_fields, Transformation::in_domain)(input)
+}
+
 fn parse_conversion(input: &str) -> IResult<&str, Conversion> {
     let (input, (from, to)) =
         terminated(separated_pair(alpha1, tag("-to-"), alpha1), tag(" map:"))(input)?;
@@ -111,31 +364,41 @@ fn parse_almanac(input: &str) -> IResult<&str, Almanac> {
     Ok((input, Almanac { seeds, conversions }))
 }
 
-fn parse_seed_ranges(input: &str) -> IResult<&str, Vec<u64>> {
-    let (input, seed_ranges) = delimited(
+fn parse_seed_range(input: &str) -> IResult<&str, Range<u128>> {
+    let (input, (start, len)) = separated_pair(complete::u64, space1, complete::u64)(input)?;
+    let (start, len) = (u128::from(start), u128::from(len));
+    Ok((input, start..start + len))
+}
+
+fn parse_seed_ranges(input: &str) -> IResult<&str, Vec<Range<u128>>> {
+    let (input, seeds) = delimited(
         tag("seeds: "),
-        separated_list1(space1, separated_pair(complete::u64, space1, complete::u64)),
+        separated_list1(
+            space1,
+            verify(parse_seed_range, |range: &Range<u128>| {
+                range.end <= DOMAIN_END
+            }),
+        ),
         line_ending,
     )(input)?;
-    let seeds = seed_ranges.iter().fold(vec![], |mut acc, (start, len)| {
-        acc.extend(*start..=(*start+*len));
-        acc
-    });
     Ok((input, seeds))
 }
+
 fn parse_almanac_with_seed_ranges(input: &str) -> IResult<&str, Almanac> {
     let (input, (seeds, conversions)) =
         separated_pair(parse_seed_ranges, line_ending, parse_conversions)(input)?;
     Ok((input, Almanac { seeds, conversions }))
 }
 
-fn process_1(input: &str) -> u64 {
+fn process_1(input: &str) -> u128 {
     let almanac = parse_almanac(input).expect("almanac to parse").1;
     almanac.lowest_location()
 }
 
-fn process_2(input: &str) -> u64 {
-    let almanac = parse_almanac_with_seed_ranges(input).expect("almanac to parse").1;
+fn process_2(input: &str) -> u128 {
+    let almanac = parse_almanac_with_seed_ranges(input)
+        .expect("almanac to parse")
+        .1;
     almanac.lowest_location()
 }
 
@@ -224,3 +487,182 @@ humidity-to-location map:
 
     assert_eq!(46, process_2(INPUT));
 }
+
+#[cfg(test)]
+const TEST_INPUT: &str = "seeds: 79 14 55 13
+
+seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4
+
+";
+
+#[test]
+fn test_map_ranges() {
+    let almanac = parse_almanac(TEST_INPUT).expect("almanac to parse").1;
+    let soil = PiecewiseMap::from(&almanac.conversions[0]);
+    assert_eq!(
+        vec![45..50, 52..57, 50..52, 100..110],
+        soil.map_ranges(&[45..55, 98..110])
+    );
+    assert_eq!(Vec::<Range<u128>>::new(), soil.map_ranges(&[7..7, 20..20]));
+
+    let almanac = parse_almanac_with_seed_ranges(TEST_INPUT)
+        .expect("almanac to parse")
+        .1;
+    assert_eq!(vec![79..93, 55..68], almanac.seeds);
+    let locations = almanac
+        .chain("seed", "location")
+        .expect("seed to location")
+        .map_ranges(&almanac.seeds);
+    assert_eq!(27, locations.iter().map(|r| r.end - r.start).sum::<u128>());
+}
+
+#[test]
+fn test_compose() {
+    let almanac = parse_almanac(TEST_INPUT).expect("almanac to parse").1;
+    let composed = almanac.chain("seed", "location").expect("seed to location");
+    assert_eq!(
+        vec![82, 43, 86, 35],
+        [79, 14, 55, 13].map(|seed| composed.get(seed))
+    );
+
+    let maps = almanac
+        .conversions
+        .iter()
+        .map(PiecewiseMap::from)
+        .collect::<Vec<_>>();
+    for seed in 0..200 {
+        let stepwise = maps.iter().fold(seed, |value, map| map.get(value));
+        assert_eq!(stepwise, composed.get(seed));
+    }
+    assert!(composed
+        .segments
+        .windows(2)
+        .all(|w| w[0].source.end == w[1].source.start));
+}
+
+#[test]
+fn test_lookup() {
+    let almanac = parse_almanac(TEST_INPUT).expect("almanac to parse").1;
+    assert_eq!(Ok(vec![82]), almanac.seeds_for_location(46));
+    assert_eq!(Ok(vec![81]), almanac.lookup("seed", "soil", 79));
+    assert_eq!(Ok(vec![79]), almanac.lookup("soil", "seed", 81));
+    assert_eq!(Ok(vec![98]), almanac.lookup("soil", "seed", 50));
+    assert_eq!(Ok(vec![96]), almanac.lookup("soil", "seed", 98));
+    assert_eq!(Ok(vec![81]), almanac.lookup("humidity", "soil", 78));
+    assert_eq!(Ok(vec![78]), almanac.lookup("soil", "humidity", 81));
+
+    let folded = parse_almanac("seeds: 1\n\nseed-to-soil map:\n5 0 2\n")
+        .expect("almanac to parse")
+        .1;
+    assert_eq!(Ok(vec![0, 5]), folded.lookup("soil", "seed", 5));
+    assert_eq!(Ok(vec![]), folded.lookup("soil", "seed", 0));
+
+    for location in [35, 43, 82, 86] {
+        let seeds = almanac.seeds_for_location(location).expect("seeds");
+        assert!(seeds
+            .iter()
+            .all(|seed| almanac.lookup("seed", "location", *seed) == Ok(vec![location])));
+    }
+}
+
+#[test]
+fn test_errors() {
+    let almanac = parse_almanac(TEST_INPUT).expect("almanac to parse").1;
+    assert_eq!(
+        Err(AlmanacError::Missing {
+            from: "location".to_string(),
+            to: "seed".to_string()
+        }),
+        almanac.chain("location", "seed")
+    );
+    assert!(matches!(
+        almanac.lookup("seed", "weather", 1),
+        Err(AlmanacError::Missing { .. })
+    ));
+
+    let cyclic = parse_almanac(
+        "seeds: 1
+
+seed-to-soil map:
+0 1 1
+
+soil-to-seed map:
+1 0 1
+",
+    )
+    .expect("almanac to parse")
+    .1;
+    assert!(matches!(
+        cyclic.chain("seed", "soil"),
+        Err(AlmanacError::Cycle(_))
+    ));
+}
+
+#[test]
+fn test_domain_edges() {
+    let max = u64::MAX as u128;
+    let almanac = parse_almanac(
+        "seeds: 18446744073709551615 18446744073709551614
+
+seed-to-location map:
+0 18446744073709551615 1
+",
+    )
+    .expect("almanac to parse")
+    .1;
+    let location = almanac.chain("seed", "location").expect("seed to location");
+    assert_eq!(0, location.get(max));
+    assert_eq!(max - 1, location.get(max - 1));
+    assert_eq!(Ok(vec![0, max]), almanac.seeds_for_location(0));
+    assert_eq!(Ok(vec![]), almanac.seeds_for_location(max));
+    assert_eq!(0, almanac.lowest_location());
+
+    let ranges = parse_almanac_with_seed_ranges(
+        "seeds: 18446744073709551614 2
+
+seed-to-soil map:
+5 18446744073709551615 1
+",
+    )
+    .expect("almanac to parse")
+    .1;
+    assert_eq!(vec![max - 1..DOMAIN_END], ranges.seeds);
+    assert_eq!(
+        5,
+        ranges.chain("seed", "soil").expect("seed to soil").get(max)
+    );
+
+    assert!(parse_seed_ranges("seeds: 18446744073709551615 2\n").is_err());
+    assert!(parse_transformation("0 18446744073709551615 2\n").is_err());
+    assert!(parse_transformation("18446744073709551615 0 2\n").is_err());
+}
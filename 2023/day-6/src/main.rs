@@ -1,71 +1,99 @@
 use nom::{
     bytes::complete::tag,
-    character::complete::{self, digit1, line_ending, space1},
+    character::complete::{digit1, line_ending, space1},
     multi::separated_list1,
-    sequence::delimited,
+    sequence::{delimited, preceded},
     IResult,
 };
+use num_bigint::BigUint;
+use num_traits::Zero;
 
 const INPUT: &str = include_str!("input");
 
-fn process_1(input: &str) -> u64 {
+fn process_1(input: &str) -> BigUint {
     let records = parse_records(input, Kerning::Bad).unwrap().1;
     records.iter().map(|record| record.hold_count()).product()
 }
 
-fn process_2(input: &str) -> u64 {
-    let records = parse_records(input, Kerning::Good).expect("input to parse into Record").1;
+fn process_2(input: &str) -> BigUint {
+    let records = parse_records(input, Kerning::Good)
+        .expect("input to parse into Record")
+        .1;
     records.iter().map(|record| record.hold_count()).product()
 }
 
 struct Record {
-    time: u64,
-    distance: u64,
+    time: BigUint,
+    distance: BigUint,
 }
 
 impl Record {
-    fn hold_count(&self) -> u64 {
-        (0..self.time)
-            .into_iter()
-            .filter(|time| time * (self.time - time) > self.distance)
-            .count() as u64
+    fn wins(&self, hold: &BigUint) -> bool {
+        hold * (&self.time - hold) > self.distance
+    }
+
+    /// Counts the holds strictly inside the roots of `hold * (time - hold) = distance`.
+    fn hold_count(&self) -> BigUint {
+        let square = &self.time * &self.time;
+        let four_distance = &self.distance << 2;
+        if square <= four_distance {
+            return BigUint::zero();
+        }
+
+        let half = &self.time >> 1;
+        let mut hold = (&self.time - (square - four_distance).sqrt()) >> 1;
+        while !self.wins(&hold) && hold <= half {
+            hold += 1u8;
+        }
+        while !hold.is_zero() && self.wins(&(&hold - 1u8)) {
+            hold -= 1u8;
+        }
+
+        if self.wins(&hold) {
+            &self.time + 1u8 - (hold << 1)
+        } else {
+            BigUint::zero()
+        }
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum Kerning {
     Bad,
     Good,
 }
 
+fn parse_values<'a>(
+    label: &'static str,
+    kerning: Kerning,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<BigUint>> {
+    move |input| {
+        let (input, digits) = preceded(
+            tag(label),
+            delimited(space1, separated_list1(space1, digit1), line_ending),
+        )(input)?;
+        let values = match kerning {
+            Kerning::Bad => digits
+                .iter()
+                .map(|d| d.parse().expect("digits to parse into BigUint"))
+                .collect(),
+            Kerning::Good => vec![digits
+                .concat()
+                .parse()
+                .expect("digits to parse into BigUint")],
+        };
+        Ok((input, values))
+    }
+}
+
 fn parse_records(input: &str, kerning: Kerning) -> IResult<&str, Vec<Record>> {
-    let (input, _) = tag("Time:")(input)?;
-    let (input, times) = if kerning == Kerning::Bad {
-        delimited(space1, separated_list1(space1, complete::u64), line_ending)(input)?
-    } else {
-        let (input, digits) =
-            delimited(space1, separated_list1(space1, digit1), line_ending)(input)?;
-        let times = vec![digits.join("").parse().expect("digits to parse into u64")];
-        (input, times)
-    };
-
-    let (input, _) = tag("Distance:")(input)?;
-    let (input, distances) = if kerning == Kerning::Bad {
-        delimited(space1, separated_list1(space1, complete::u64), line_ending)(input)?
-    } else {
-        let (input, digits) =
-            delimited(space1, separated_list1(space1, digit1), line_ending)(input)?;
-        let distances = vec![digits.join("").parse().expect("digits to parse into u64")];
-        (input, distances)
-    };
+    let (input, times) = parse_values("Time:", kerning)(input)?;
+    let (input, distances) = parse_values("Distance:", kerning)(input)?;
 
     let records = times
-        .iter()
-        .zip(distances.iter())
-        .map(|(time, distance)| Record {
-            time: *time,
-            distance: *distance,
-        })
+        .into_iter()
+        .zip(distances)
+        .map(|(time, distance)| Record { time, distance })
         .collect();
 
     Ok((input, records))
@@ -82,7 +110,7 @@ fn test_process_1() {
 Distance:  9  40  200
 
 ";
-    assert_eq!(288, process_1(INPUT));
+    assert_eq!(BigUint::from(288u32), process_1(INPUT));
 }
 
 #[test]
@@ -91,5 +119,47 @@ fn test_process_2() {
 Distance:  9  40  200
 
 ";
-    assert_eq!(71503, process_2(INPUT));
+    assert_eq!(BigUint::from(71503u32), process_2(INPUT));
+}
+
+#[cfg(test)]
+fn record(time: &str, distance: &str) -> Record {
+    Record {
+        time: time.parse().expect("time to parse"),
+        distance: distance.parse().expect("distance to parse"),
+    }
+}
+
+#[test]
+fn test_hold_count() {
+    for time in 0..40u32 {
+        for distance in 0..=time * time / 4 + 1 {
+            let expected = (0..=time)
+                .filter(|hold| hold * (time - hold) > distance)
+                .count();
+            assert_eq!(
+                BigUint::from(expected),
+                record(&time.to_string(), &distance.to_string()).hold_count(),
+                "time {time}, distance {distance}"
+            );
+        }
+    }
+
+    assert_eq!(BigUint::from(9u8), record("30", "200").hold_count());
+    assert_eq!(BigUint::zero(), record("4", "4").hold_count());
+}
+
+#[test]
+fn test_long_race() {
+    let time = format!("2{}", "0".repeat(40));
+    let half = format!("1{}", "0".repeat(40)).parse::<BigUint>().unwrap();
+
+    let tie = (&half * &half).to_string();
+    assert_eq!(BigUint::zero(), record(&time, &tie).hold_count());
+
+    let below = (&half * &half - 1u8).to_string();
+    assert_eq!(BigUint::from(1u8), record(&time, &below).hold_count());
+
+    let near = record(&time, &(&half * &half - 25u8).to_string());
+    assert_eq!(BigUint::from(9u8), near.hold_count());
 }
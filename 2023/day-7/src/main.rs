@@ -1,163 +1,149 @@
 use std::collections::HashMap;
 
 use nom::{
-    branch::alt,
-    bytes::complete::{tag, take},
-    character::complete::{self, line_ending, space1},
-    combinator::{map_parser, value},
-    multi::{count, separated_list1},
+    character::complete::{self, alphanumeric1, line_ending, space1},
+    combinator::map,
+    multi::separated_list1,
     sequence::separated_pair,
     IResult,
 };
+use thiserror::Error;
 
 const INPUT: &str = include_str!("input");
 
 #[derive(Clone, Debug, PartialEq)]
 struct Hand {
-    cards: Vec<u8>,
+    cards: Vec<char>,
     bid: u32,
 }
 
-const JOKER: u8 = 11u8;
+/// Group sizes in descending order, so categories compare lexicographically:
+/// five of a kind `[5]` beats four of a kind `[4, 1]` beats a full house
+/// `[3, 2]`, and so on for any hand size.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Category(Vec<usize>);
 
-#[derive(Debug, PartialEq)]
-struct HandWithWildcard(Hand);
-
-impl HandWithWildcard {
-    fn new(mut hand: Hand) -> Self {
-        hand.cards.iter_mut().for_each(|c| {
-            if *c == JOKER {
-                *c = 1u8;
-            }
-        });
-        Self(hand)
+impl Category {
+    fn of(cards: &[char]) -> Self {
+        let mut counts = cards
+            .iter()
+            .fold(HashMap::new(), |mut acc, c| {
+                *acc.entry(c).or_insert(0) += 1;
+                acc
+            })
+            .into_values()
+            .collect::<Vec<usize>>();
+        counts.sort_by(|a, b| b.cmp(a));
+        Self(counts)
     }
+}
 
-    fn rank(&self) -> Kind {
-        let jokers = self.0.cards.iter().filter(|c| **c == 1u8).count();
-
-        if jokers == 0 {
-            return self.0.rank();
-        }
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Scored {
+    category: Category,
+    strengths: Vec<usize>,
+    bid: u32,
+}
 
-        let rank = match (jokers, self.0.rank()) {
-            (_, Kind::FourOfAKind) => Kind::FiveOfAKind,
-            (_, Kind::FullHouse) => Kind::FiveOfAKind,
+#[derive(Debug, Error, PartialEq)]
+#[error("card '{0}' is not ranked by these rules")]
+struct UnknownCard(char);
 
-            (1, Kind::TwoPair) => Kind::FullHouse,
-            (2, Kind::TwoPair) => Kind::FourOfAKind,
+#[derive(Clone, Debug)]
+struct Rules {
+    /// Card labels from weakest to strongest.
+    order: Vec<char>,
+    wildcards: Vec<char>,
+}
 
-            (1, Kind::ThreeOfAKind) => Kind::FourOfAKind,
-            (3, Kind::ThreeOfAKind) => Kind::FourOfAKind,
+impl Rules {
+    fn standard() -> Self {
+        Self {
+            order: "23456789TJQKA".chars().collect(),
+            wildcards: vec![],
+        }
+    }
 
-            (1, Kind::TwoOfAKind) => Kind::ThreeOfAKind,
-            (2, Kind::TwoOfAKind) => Kind::ThreeOfAKind,
+    fn jokers() -> Self {
+        Self {
+            order: "J23456789TQKA".chars().collect(),
+            wildcards: vec!['J'],
+        }
+    }
 
-            (1, Kind::OneAKind) => Kind::TwoOfAKind,
+    fn strength(&self, card: char) -> Result<usize, UnknownCard> {
+        self.order
+            .iter()
+            .position(|c| *c == card)
+            .ok_or(UnknownCard(card))
+    }
 
-            (_, rank) => rank,
-        };
+    /// Tries every way of standing the wildcards in for the other cards held,
+    /// or for the strongest card when the whole hand is wild.
+    fn category(&self, cards: &[char]) -> Category {
+        let (wild, mut fixed): (Vec<char>, Vec<char>) =
+            cards.iter().partition(|c| self.wildcards.contains(c));
 
-        rank
-    }
-}
+        let mut candidates = fixed.clone();
+        candidates.sort_unstable();
+        candidates.dedup();
+        if candidates.is_empty() {
+            candidates.extend(self.order.last());
+        }
 
-impl PartialOrd for HandWithWildcard {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        let rank_order = self.rank().partial_cmp(&other.rank());
-        if rank_order == Some(std::cmp::Ordering::Equal) {
-            self.0
-                .cards
+        fn best(fixed: &mut Vec<char>, wild: usize, candidates: &[char]) -> Category {
+            if wild == 0 {
+                return Category::of(fixed);
+            }
+            candidates
                 .iter()
-                .zip(other.0.cards.iter())
-                .find_map(|(c1, c2)| {
-                    let cmp = c1.partial_cmp(c2);
-                    if cmp == Some(std::cmp::Ordering::Equal) {
-                        None
-                    } else {
-                        Some(cmp)
-                    }
+                .map(|c| {
+                    fixed.push(*c);
+                    let category = best(fixed, wild - 1, candidates);
+                    fixed.pop();
+                    category
                 })
-                .expect("hands to be different")
-        } else {
-            rank_order
+                .max()
+                .expect("candidates to stand in for wildcards")
         }
-    }
-}
-
-#[derive(Debug, PartialEq, PartialOrd)]
-enum Kind {
-    OneAKind,
-    TwoOfAKind,
-    TwoPair,
-    ThreeOfAKind,
-    FullHouse,
-    FourOfAKind,
-    FiveOfAKind,
-}
 
-impl Hand {
-    fn rank(&self) -> Kind {
-        let mut counts: Vec<u8> = self
-            .cards
-            .iter()
-            .fold(HashMap::new(), |mut acc, c| {
-                *acc.entry(c).or_insert(0u8) += 1u8;
-                acc
-            })
-            .values()
-            .map(|v| *v)
-            .collect();
-        counts.sort();
-        match counts.as_slice() {
-            [5] => Kind::FiveOfAKind,
-            [1, 4] => Kind::FourOfAKind,
-            [2, 3] => Kind::FullHouse,
-            [1, 1, 3] => Kind::ThreeOfAKind,
-            [1, 2, 2] => Kind::TwoPair,
-            [1, 1, 1, 2] => Kind::TwoOfAKind,
-            [1, 1, 1, 1, 1] => Kind::OneAKind,
-            _ => panic!("invalid hand"),
-        }
+        best(&mut fixed, wild.len(), &candidates)
     }
-}
 
-impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        let rank_order = self.rank().partial_cmp(&other.rank());
-        if rank_order == Some(std::cmp::Ordering::Equal) {
-            self.cards
+    fn score(&self, hand: &Hand) -> Result<Scored, UnknownCard> {
+        Ok(Scored {
+            strengths: hand
+                .cards
                 .iter()
-                .zip(other.cards.iter())
-                .find_map(|(c1, c2)| {
-                    let cmp = c1.partial_cmp(c2);
-                    if cmp == Some(std::cmp::Ordering::Equal) {
-                        None
-                    } else {
-                        Some(cmp)
-                    }
-                })
-                .expect("hands to be different")
-        } else {
-            rank_order
-        }
+                .map(|c| self.strength(*c))
+                .collect::<Result<_, _>>()?,
+            category: self.category(&hand.cards),
+            bid: hand.bid,
+        })
     }
-}
 
-fn parse_card(input: &str) -> IResult<&str, u8> {
-    alt((
-        value(10u8, tag("T")),
-        value(JOKER, tag("J")),
-        value(12u8, tag("Q")),
-        value(13u8, tag("K")),
-        value(14u8, tag("A")),
-        map_parser(take(1usize), complete::u8),
-    ))(input)
+    fn winnings(&self, hands: &[Hand]) -> Result<u32, UnknownCard> {
+        let mut scored = hands
+            .iter()
+            .map(|h| self.score(h))
+            .collect::<Result<Vec<_>, _>>()?;
+        scored.sort();
+        Ok(scored
+            .iter()
+            .zip(1u32..)
+            .map(|(hand, rank)| rank * hand.bid)
+            .sum())
+    }
 }
 
 fn parse_hand(input: &str) -> IResult<&str, Hand> {
-    let (input, (cards, bid)) = separated_pair(count(parse_card, 5), space1, complete::u32)(input)?;
-    Ok((input, Hand { cards, bid }))
+    map(
+        separated_pair(alphanumeric1, space1, complete::u32),
+        |(cards, bid): (&str, u32)| Hand {
+            cards: cards.chars().collect(),
+            bid,
+        },
+    )(input)
 }
 
 fn parse_input(input: &str) -> IResult<&str, Vec<Hand>> {
@@ -165,32 +151,21 @@ fn parse_input(input: &str) -> IResult<&str, Vec<Hand>> {
 }
 
 fn process_1(input: &str) -> u32 {
-    let mut hands = parse_input(input)
+    let hands = parse_input(input)
         .expect("input to be parsed as Hand structs")
         .1;
-
-    hands.sort_by(|h1, h2| h1.partial_cmp(h2).expect("hands to be different"));
-    hands
-        .iter()
-        .enumerate()
-        .map(|(i, card)| (i as u32 + 1u32) * card.bid)
-        .sum()
+    Rules::standard()
+        .winnings(&hands)
+        .expect("hands to use known cards")
 }
 
 fn process_2(input: &str) -> u32 {
-    let mut hands = parse_input(input)
+    let hands = parse_input(input)
         .expect("input to be parsed as Hand structs")
-        .1
-        .iter()
-        .map(|h| HandWithWildcard::new(h.clone()))
-        .collect::<Vec<_>>();
-
-    hands.sort_by(|h1, h2| h1.partial_cmp(h2).expect("hands to be different"));
-    hands
-        .iter()
-        .enumerate()
-        .map(|(i, card)| (i as u32 + 1u32) * card.0.bid)
-        .sum()
+        .1;
+    Rules::jokers()
+        .winnings(&hands)
+        .expect("hands to use known cards")
 }
 
 fn main() {
@@ -221,3 +196,65 @@ QQQJA 483
 ";
     assert_eq!(5905, process_2(INPUT))
 }
+
+#[cfg(test)]
+fn category(rules: &Rules, cards: &str) -> Vec<usize> {
+    rules.category(&cards.chars().collect::<Vec<_>>()).0
+}
+
+#[test]
+fn test_wildcards() {
+    let rules = Rules::jokers();
+    assert_eq!(vec![5], category(&rules, "JJJJJ"));
+    assert_eq!(vec![4, 1], category(&rules, "T55J5"));
+    assert_eq!(vec![4, 1], category(&rules, "KTJJT"));
+    assert_eq!(vec![3, 2], category(&rules, "KKJ22"));
+    assert_eq!(vec![2, 1, 1, 1], category(&rules, "2345J"));
+    assert_eq!(vec![2, 2, 1], category(&Rules::standard(), "KTJJT"));
+
+    let rules = Rules {
+        wildcards: vec!['J', '2'],
+        ..Rules::jokers()
+    };
+    assert_eq!(vec![3, 1, 1], category(&rules, "2345J"));
+    assert_eq!(vec![5], category(&rules, "22JJ2"));
+
+    let score = |cards: &str| {
+        let hand = Hand {
+            cards: cards.chars().collect(),
+            bid: 0,
+        };
+        Rules::jokers().score(&hand).expect("known cards")
+    };
+    assert!(score("JKKK2") < score("QQQQ2"));
+}
+
+#[test]
+fn test_unknown_cards() {
+    let hands = parse_input("32T3K 765\nKK1X7 28")
+        .expect("hands to parse")
+        .1;
+    assert_eq!(Err(UnknownCard('1')), Rules::standard().winnings(&hands));
+    assert_eq!(Err(UnknownCard('1')), Rules::jokers().score(&hands[1]));
+    assert!(Rules::standard().score(&hands[0]).is_ok());
+}
+
+#[test]
+fn test_six_card_hands() {
+    let rules = Rules::standard();
+    assert_eq!(vec![3, 3], category(&rules, "AAAKKK"));
+    assert!(Category(vec![4, 1, 1]) > Category(vec![3, 3]));
+    assert!(Category(vec![3, 3]) > Category(vec![3, 2, 1]));
+
+    let hands = parse_input("AAAKKK 1\nAAAAK2 10\n23456A 100\n22JJ33 1000")
+        .expect("six card hands to parse")
+        .1;
+    assert_eq!(
+        Ok(100 + 2 + 10 * 3 + 1000 * 4),
+        Rules::jokers().winnings(&hands)
+    );
+    assert_eq!(
+        Ok(100 + 1000 * 2 + 3 + 10 * 4),
+        Rules::standard().winnings(&hands)
+    );
+}
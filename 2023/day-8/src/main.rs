@@ -1,4 +1,4 @@
-use num::integer::lcm;
+use num::integer::{ExtendedGcd, Integer};
 use std::collections::HashMap;
 
 use nom::{
@@ -44,17 +44,141 @@ impl Map {
         steps
     }
 
-    fn ghost_steps(&self) -> u64 {
-        self
+    /// Follows a ghost until it repeats a `(node, instruction)` state.
+    fn walk(&self, start: &str) -> Walk {
+        let mut seen = HashMap::new();
+        let mut hits = vec![];
+        let mut current = start;
+        let mut step = 0u64;
+        loop {
+            let index = step as usize % self.instructions.len();
+            if let Some(first) = seen.insert((current, index), step) {
+                let (prefix_hits, cycle_hits) = hits.into_iter().partition(|hit| *hit < first);
+                return Walk {
+                    offset: first,
+                    length: step - first,
+                    prefix_hits,
+                    cycle_hits,
+                };
+            }
+            if current.ends_with('Z') {
+                hits.push(step);
+            }
+            let network = self.network.get(current).expect("network to exist");
+            current = match self.instructions[index] {
+                Direction::Left => &network.left,
+                Direction::Right => &network.right,
+            };
+            step += 1;
+        }
+    }
+
+    fn ghost_steps(&self) -> Option<u64> {
+        let walks = self
             .network
             .keys()
+            .filter(|k| k.ends_with('A'))
+            .map(|k| self.walk(k))
+            .collect::<Vec<_>>();
+
+        let mut early = walks
+            .iter()
+            .flat_map(|w| w.prefix_hits.iter().copied())
+            .collect::<Vec<_>>();
+        early.sort_unstable();
+        if let Some(step) = early
+            .into_iter()
+            .find(|step| walks.iter().all(|w| w.hits(*step)))
+        {
+            return Some(step);
+        }
+
+        let mut congruences = vec![(0u64, 1u64)];
+        for walk in &walks {
+            let mut combined = vec![];
+            for congruence in &congruences {
+                for hit in &walk.cycle_hits {
+                    if let Some(congruence) =
+                        crt(*congruence, (hit % walk.length, walk.length)).ok()?
+                    {
+                        combined.push(congruence);
+                    }
+                }
+            }
+            if combined.is_empty() {
+                return None;
+            }
+            congruences = combined;
+        }
+
+        // The first step at or after every walk has entered its cycle.
+        let floor = walks.iter().map(|w| w.offset).max().unwrap_or(0);
+        congruences
             .into_iter()
-            .filter(|k| k.ends_with("A"))
-            .map(|k| self.steps(k) as u64)
-            .fold(1, |acc, count| lcm(acc, count))
+            .filter_map(|(residue, modulus)| {
+                let behind = floor % modulus;
+                let shift = if residue >= behind {
+                    residue - behind
+                } else {
+                    modulus - behind + residue
+                };
+                floor.checked_add(shift)
+            })
+            .min()
     }
 }
 
+/// A ghost's path: Z hits before its cycle, then hits repeating every
+/// `length` steps from `offset`.
+#[derive(Debug, PartialEq)]
+struct Walk {
+    offset: u64,
+    length: u64,
+    prefix_hits: Vec<u64>,
+    cycle_hits: Vec<u64>,
+}
+
+impl Walk {
+    fn hits(&self, step: u64) -> bool {
+        if step < self.offset {
+            self.prefix_hits.contains(&step)
+        } else {
+            self.cycle_hits
+                .iter()
+                .any(|hit| step >= *hit && (step - hit).is_multiple_of(self.length))
+        }
+    }
+}
+
+/// The combined modulus of two congruences does not fit in a `u64`.
+#[derive(Debug, PartialEq)]
+struct Overflow;
+
+/// Combines `x = a1 (mod m1)` and `x = a2 (mod m2)`, if they agree. Expects
+/// each residue to be below its modulus.
+fn crt((a1, m1): (u64, u64), (a2, m2): (u64, u64)) -> Result<Option<(u64, u64)>, Overflow> {
+    let ExtendedGcd { gcd, x, .. } = i128::from(m1).extended_gcd(&i128::from(m2));
+    let difference = i128::from(a2) - i128::from(a1);
+    if difference % gcd != 0 {
+        return Ok(None);
+    }
+
+    let step = i128::from(m2) / gcd;
+    let modulus = i128::from(m1).checked_mul(step).ok_or(Overflow)?;
+    let modulus = u64::try_from(modulus).map_err(|_| Overflow)?;
+
+    // Both factors are reduced below `step`, which fits in a `u64`, so
+    // their product fits in a `u128`.
+    let reduce = |v: i128| u128::try_from(v.rem_euclid(step)).map_err(|_| Overflow);
+    let step = u128::try_from(step).map_err(|_| Overflow)?;
+    let multiple = reduce(difference / gcd)? * reduce(x)? % step;
+    let residue = (u128::from(a1) + multiple * u128::from(m1)) % u128::from(modulus);
+    Ok(Some((
+        u64::try_from(residue).map_err(|_| Overflow)?,
+        modulus,
+    )))
+}
+
 fn parse_directions(input: &str) -> IResult<&str, Vec<Direction>> {
     terminated(
         many1(alt((
@@ -109,7 +233,7 @@ fn process_1(input: &str) -> u32 {
 fn process_2(input: &str) -> u64 {
     let map = parse_map(input).expect("map to parse").1;
 
-    map.ghost_steps()
+    map.ghost_steps().expect("ghosts to meet")
 }
 
 fn main() {
@@ -161,3 +285,102 @@ XXX = (XXX, XXX)
 ";
     assert_eq!(6, process_2(INPUT))
 }
+
+#[test]
+fn test_walk() {
+    const INPUT: &str = "LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)
+
+";
+    let map = parse_map(INPUT).expect("map to parse").1;
+    assert_eq!(
+        Walk {
+            offset: 1,
+            length: 6,
+            prefix_hits: vec![],
+            cycle_hits: vec![3, 6],
+        },
+        map.walk("22A")
+    );
+    assert_eq!(
+        Walk {
+            offset: 1,
+            length: 2,
+            prefix_hits: vec![],
+            cycle_hits: vec![2],
+        },
+        map.walk("11A")
+    );
+}
+
+#[test]
+fn test_misaligned_cycles() {
+    const INPUT: &str = "L
+
+1A = (1Z, 1Z)
+1Z = (1X, 1X)
+1X = (1Z, 1Z)
+2A = (2B, 2B)
+2B = (2Z, 2Z)
+2Z = (2C, 2C)
+2C = (2D, 2D)
+2D = (2Z, 2Z)
+
+";
+    let map = parse_map(INPUT).expect("map to parse").1;
+    assert_eq!(Some(5), map.ghost_steps());
+
+    let map = parse_map(&INPUT.replace("2D = (2Z, 2Z)", "2D = (2B, 2B)"))
+        .expect("map to parse")
+        .1;
+    assert_eq!(None, map.ghost_steps());
+}
+
+#[test]
+fn test_prefix_hits() {
+    const INPUT: &str = "L
+
+1A = (1Z, 1Z)
+1Z = (1X, 1X)
+1X = (1Z, 1Z)
+3A = (3Z, 3Z)
+3Z = (3B, 3B)
+3B = (3B, 3B)
+
+";
+    let map = parse_map(INPUT).expect("map to parse").1;
+    assert_eq!(vec![1], map.walk("3A").prefix_hits);
+    assert_eq!(Some(1), map.ghost_steps());
+
+    let map = parse_map(&INPUT.replace("1A = (1Z, 1Z)", "1A = (1X, 1X)"))
+        .expect("map to parse")
+        .1;
+    assert_eq!(None, map.ghost_steps());
+}
+
+#[test]
+fn test_crt() {
+    assert_eq!(Ok(Some((5, 6))), crt((1, 2), (2, 3)));
+    assert_eq!(Ok(Some((10, 12))), crt((2, 4), (4, 6)));
+    assert_eq!(Ok(None), crt((1, 4), (2, 6)));
+
+    let (m1, m2) = (1 << 32, (1 << 32) - 1);
+    let (residue, modulus) = crt((7, m1), (11, m2))
+        .expect("modulus to fit")
+        .expect("coprime moduli to agree");
+    assert_eq!(m1 * m2, modulus);
+    assert_eq!((7, 11), (residue % m1, residue % m2));
+
+    let big = u64::MAX - 58; // the largest prime below 2^64
+    assert_eq!(Ok(Some((big - 1, big))), crt((0, 1), (big - 1, big)));
+    assert_eq!(Err(Overflow), crt((0, 2), (1, big)));
+    assert_eq!(Err(Overflow), crt((0, 1 << 40), (1, (1 << 40) - 1)));
+}
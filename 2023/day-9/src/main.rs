@@ -1,45 +1,112 @@
 use nom::{
     character::complete::{self, line_ending, space1},
-    multi::{separated_list1, many1},
+    combinator::map,
+    multi::{many1, separated_list1},
     sequence::terminated,
-    IResult, combinator::map,
+    IResult,
 };
+use num::{BigInt, BigRational, ToPrimitive, Zero};
 
 const INPUT: &str = include_str!("input");
 
-struct History {
-    elements: Vec<i64>,
+#[derive(Debug, PartialEq)]
+enum FitError {
+    Empty,
+    /// The differences never settled to zero, so any degree below `len` is
+    /// unconfirmed.
+    NotPolynomial {
+        len: usize,
+    },
 }
 
-impl History {
-    fn next_value(&self) -> i64 {
-        History::next(&self.elements)
+/// The Newton forward differences of a sequence sampled at `0, 1, 2, ...`,
+/// so `f(x) = sum(differences[j] * binomial(x, j))`.
+#[derive(Clone, Debug, PartialEq)]
+struct Polynomial {
+    differences: Vec<BigInt>,
+}
+
+impl Polynomial {
+    fn fit(values: &[i64]) -> Result<Self, FitError> {
+        if values.is_empty() {
+            return Err(FitError::Empty);
+        }
+
+        let mut row = values.iter().map(|v| BigInt::from(*v)).collect::<Vec<_>>();
+        let mut differences = vec![];
+        while !row.iter().all(Zero::is_zero) {
+            if row.len() == 1 {
+                return Err(FitError::NotPolynomial { len: values.len() });
+            }
+            differences.push(row[0].clone());
+            row = row.windows(2).map(|w| &w[1] - &w[0]).collect();
+        }
+
+        Ok(Self { differences })
     }
 
-    fn prev_value(&self) -> i64 {
-        History::prev(&self.elements)
+    /// `None` for the zero polynomial.
+    #[allow(dead_code)]
+    fn degree(&self) -> Option<usize> {
+        self.differences.len().checked_sub(1)
     }
 
-    fn next(elements: &Vec<i64>) -> i64 {
-        if elements.iter().all(|e| e == &0i64)  {
-            return 0i64;
+    fn at(&self, x: i64) -> BigInt {
+        let mut binomial = BigInt::from(1);
+        let mut value = BigInt::zero();
+        for (j, difference) in self.differences.iter().enumerate() {
+            value += difference * &binomial;
+            binomial = binomial * (x - j as i64) / (j as i64 + 1);
         }
+        value
+    }
 
-        let last = elements.last().expect("elements to not be empty");
-        let sub = elements.windows(2).map(|w| w[1] - w[0]).collect();
+    /// Coefficients from the constant term upwards.
+    #[allow(dead_code)]
+    fn coefficients(&self) -> Vec<BigRational> {
+        let mut coefficients = vec![BigRational::zero(); self.differences.len()];
+        let mut binomial = vec![BigRational::from_integer(1.into())];
+        for (j, difference) in self.differences.iter().enumerate() {
+            let difference = BigRational::from_integer(difference.clone());
+            for (coefficient, b) in coefficients.iter_mut().zip(&binomial) {
+                *coefficient += &difference * b;
+            }
+
+            let divisor = BigRational::from_integer((j + 1).into());
+            let shift = BigRational::from_integer(j.into());
+            let mut next = vec![BigRational::zero(); binomial.len() + 1];
+            for (power, b) in binomial.iter().enumerate() {
+                next[power + 1] += b / &divisor;
+                next[power] -= b * &shift / &divisor;
+            }
+            binomial = next;
+        }
+        coefficients
+    }
+}
 
-        last + History::next(&sub)
+struct History {
+    elements: Vec<i64>,
+}
+
+impl History {
+    fn polynomial(&self) -> Polynomial {
+        Polynomial::fit(&self.elements).expect("history to be polynomial")
     }
 
-    fn prev(elements: &Vec<i64>) -> i64 {
-        if elements.iter().all(|e| e == &0i64)  {
-            return 0i64;
-        }
+    fn extrapolate(&self, index: i64) -> i64 {
+        self.polynomial()
+            .at(index)
+            .to_i64()
+            .expect("extrapolated value to fit in i64")
+    }
 
-        let first = elements.first().expect("elements to not be empty");
-        let sub = elements.windows(2).map(|w| w[1] - w[0]).collect();
+    fn next_value(&self) -> i64 {
+        self.extrapolate(self.elements.len() as i64)
+    }
 
-        first - History::prev(&sub)
+    fn prev_value(&self) -> i64 {
+        self.extrapolate(-1)
     }
 }
 
@@ -88,3 +155,47 @@ fn test_process_2() {
 ";
     assert_eq!(2, process_2(INPUT))
 }
+
+#[test]
+fn test_polynomial() {
+    let polynomial = Polynomial::fit(&[10, 13, 16, 21, 30, 45]).expect("polynomial");
+    assert_eq!(Some(3), polynomial.degree());
+    assert_eq!(
+        vec![
+            BigRational::from_integer(10.into()),
+            BigRational::new(11.into(), 3.into()),
+            BigRational::from_integer((-1).into()),
+            BigRational::new(1.into(), 3.into()),
+        ],
+        polynomial.coefficients()
+    );
+    assert_eq!(BigInt::from(68), polynomial.at(6));
+    assert_eq!(BigInt::from(5), polynomial.at(-1));
+
+    let x = 1_000_000i128;
+    assert_eq!(
+        BigInt::from(10 + 3 * x + 2 * (x * (x - 1) * (x - 2) / 6)),
+        polynomial.at(x as i64)
+    );
+
+    let zero = Polynomial::fit(&[0, 0, 0]).expect("polynomial");
+    assert_eq!(None, zero.degree());
+    assert_eq!(BigInt::zero(), zero.at(-7));
+
+    let constant = Polynomial::fit(&[-4, -4]).expect("polynomial");
+    assert_eq!(Some(0), constant.degree());
+    assert_eq!(BigInt::from(-4), constant.at(100));
+}
+
+#[test]
+fn test_not_polynomial() {
+    assert_eq!(
+        Err(FitError::NotPolynomial { len: 5 }),
+        Polynomial::fit(&[1, 2, 4, 8, 16])
+    );
+    assert_eq!(
+        Err(FitError::NotPolynomial { len: 1 }),
+        Polynomial::fit(&[3])
+    );
+    assert_eq!(Err(FitError::Empty), Polynomial::fit(&[]));
+}
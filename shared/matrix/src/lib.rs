@@ -0,0 +1,102 @@
+use std::ops::{Mul, MulAssign};
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+/// A square matrix of counts, for advancing linear recurrences by many steps
+/// at once.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Matrix(pub Vec<Vec<BigUint>>);
+
+impl Matrix {
+    pub fn zero(size: usize) -> Self {
+        Self(vec![vec![BigUint::zero(); size]; size])
+    }
+
+    pub fn identity(size: usize) -> Self {
+        let mut matrix = Self::zero(size);
+        (0..size).for_each(|i| matrix.0[i][i] = BigUint::one());
+        matrix
+    }
+
+    pub fn pow(&self, mut exp: u64) -> Self {
+        let mut base = self.clone();
+        let mut acc = Self::identity(self.0.len());
+        while exp > 0 {
+            if exp & 1 == 1 {
+                acc *= &base;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = &base * &base;
+            }
+        }
+        acc
+    }
+
+    pub fn apply(&self, vector: &[BigUint]) -> Vec<BigUint> {
+        self.0
+            .iter()
+            .map(|row| row.iter().zip(vector).map(|(a, b)| a * b).sum())
+            .collect()
+    }
+}
+
+impl Mul for &Matrix {
+    type Output = Matrix;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let size = self.0.len();
+        let mut product = Matrix::zero(size);
+        for (i, row) in self.0.iter().enumerate() {
+            for (k, a) in row.iter().enumerate().filter(|(_, a)| !a.is_zero()) {
+                for j in (0..size).filter(|&j| !rhs.0[k][j].is_zero()) {
+                    product.0[i][j] += a * &rhs.0[k][j];
+                }
+            }
+        }
+        product
+    }
+}
+
+impl MulAssign<&Matrix> for Matrix {
+    fn mul_assign(&mut self, rhs: &Matrix) {
+        *self = &*self * rhs;
+    }
+}
+
+#[cfg(test)]
+fn matrix(rows: &[&[u32]]) -> Matrix {
+    Matrix(
+        rows.iter()
+            .map(|row| row.iter().map(|v| BigUint::from(*v)).collect())
+            .collect(),
+    )
+}
+
+#[test]
+fn test_mul() {
+    let a = matrix(&[&[1, 2], &[3, 4]]);
+    let b = matrix(&[&[0, 1], &[1, 0]]);
+    assert_eq!(matrix(&[&[2, 1], &[4, 3]]), &a * &b);
+    assert_eq!(a, &a * &Matrix::identity(2));
+
+    let mut c = a.clone();
+    c *= &b;
+    assert_eq!(&a * &b, c);
+}
+
+#[test]
+fn test_pow() {
+    let fibonacci = matrix(&[&[1, 1], &[1, 0]]);
+    assert_eq!(Matrix::identity(2), fibonacci.pow(0));
+    assert_eq!(fibonacci, fibonacci.pow(1));
+    assert_eq!(
+        vec![BigUint::from(89u8), BigUint::from(55u8)],
+        fibonacci.pow(10).apply(&[BigUint::one(), BigUint::zero()])
+    );
+    assert_eq!(
+        (0..13).fold(Matrix::identity(2), |acc, _| &acc * &fibonacci),
+        fibonacci.pow(13)
+    );
+}
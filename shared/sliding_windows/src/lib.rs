@@ -0,0 +1,291 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    ops::{Add, Sub},
+};
+
+pub trait SlidingWindows: Iterator + Sized {
+    /// Pairs each item with the one before it.
+    fn pairwise(self) -> Pairwise<Self>
+    where
+        Self::Item: Clone,
+    {
+        Pairwise {
+            iter: self,
+            previous: None,
+        }
+    }
+
+    /// Sums each full window of `size` items, keeping a running total.
+    fn rolling_sum(self, size: usize) -> RollingSum<Self>
+    where
+        Self::Item: Copy + Add<Output = Self::Item> + Sub<Output = Self::Item>,
+    {
+        assert!(size > 0, "window size must be positive");
+        RollingSum {
+            iter: self,
+            size,
+            window: VecDeque::with_capacity(size),
+            sum: None,
+        }
+    }
+
+    /// Smallest item of each full window of `size` items.
+    fn rolling_min(self, size: usize) -> RollingExtreme<Self>
+    where
+        Self::Item: Copy + PartialOrd,
+    {
+        RollingExtreme::new(self, size, |kept, next| kept < next)
+    }
+
+    /// Largest item of each full window of `size` items.
+    fn rolling_max(self, size: usize) -> RollingExtreme<Self>
+    where
+        Self::Item: Copy + PartialOrd,
+    {
+        RollingExtreme::new(self, size, |kept, next| kept > next)
+    }
+
+    /// Yields the number of items consumed whenever the last `size` items
+    /// are all distinct.
+    fn distinct_windows(self, size: usize) -> DistinctWindows<Self>
+    where
+        Self::Item: Clone + Eq + Hash,
+    {
+        assert!(size > 0, "window size must be positive");
+        DistinctWindows {
+            iter: self,
+            size,
+            window: VecDeque::with_capacity(size + 1),
+            counts: HashMap::new(),
+            repeated: 0,
+            index: 0,
+        }
+    }
+}
+
+impl<I: Iterator> SlidingWindows for I {}
+
+pub struct Pairwise<I: Iterator> {
+    iter: I,
+    previous: Option<I::Item>,
+}
+
+impl<I> Iterator for Pairwise<I>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    type Item = (I::Item, I::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.previous.is_none() {
+            self.previous = Some(self.iter.next()?);
+        }
+        let next = self.iter.next()?;
+        let previous = self.previous.replace(next.clone())?;
+        Some((previous, next))
+    }
+}
+
+pub struct RollingSum<I: Iterator> {
+    iter: I,
+    size: usize,
+    window: VecDeque<I::Item>,
+    sum: Option<I::Item>,
+}
+
+impl<I> Iterator for RollingSum<I>
+where
+    I: Iterator,
+    I::Item: Copy + Add<Output = I::Item> + Sub<Output = I::Item>,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let item = self.iter.next()?;
+            let mut sum = self.sum.map_or(item, |sum| sum + item);
+            self.window.push_back(item);
+            if self.window.len() > self.size {
+                sum = sum - self.window.pop_front()?;
+            }
+            self.sum = Some(sum);
+            if self.window.len() == self.size {
+                return Some(sum);
+            }
+        }
+    }
+}
+
+/// Keeps a monotonic deque of `(index, item)` so each window's extreme is
+/// at the front.
+pub struct RollingExtreme<I: Iterator> {
+    iter: I,
+    size: usize,
+    index: usize,
+    deque: VecDeque<(usize, I::Item)>,
+    keep: fn(&I::Item, &I::Item) -> bool,
+}
+
+impl<I> RollingExtreme<I>
+where
+    I: Iterator,
+{
+    fn new(iter: I, size: usize, keep: fn(&I::Item, &I::Item) -> bool) -> Self {
+        assert!(size > 0, "window size must be positive");
+        Self {
+            iter,
+            size,
+            index: 0,
+            deque: VecDeque::with_capacity(size),
+            keep,
+        }
+    }
+}
+
+impl<I> Iterator for RollingExtreme<I>
+where
+    I: Iterator,
+    I::Item: Copy,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let item = self.iter.next()?;
+            while matches!(self.deque.back(), Some((_, back)) if !(self.keep)(back, &item)) {
+                self.deque.pop_back();
+            }
+            self.deque.push_back((self.index, item));
+            self.index += 1;
+            while matches!(self.deque.front(), Some((i, _)) if i + self.size < self.index) {
+                self.deque.pop_front();
+            }
+            if self.index >= self.size {
+                return self.deque.front().map(|(_, item)| *item);
+            }
+        }
+    }
+}
+
+pub struct DistinctWindows<I: Iterator> {
+    iter: I,
+    size: usize,
+    window: VecDeque<I::Item>,
+    counts: HashMap<I::Item, usize>,
+    repeated: usize,
+    index: usize,
+}
+
+impl<I> Iterator for DistinctWindows<I>
+where
+    I: Iterator,
+    I::Item: Clone + Eq + Hash,
+{
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let item = self.iter.next()?;
+            self.index += 1;
+
+            let count = self.counts.entry(item.clone()).or_default();
+            *count += 1;
+            if *count == 2 {
+                self.repeated += 1;
+            }
+            self.window.push_back(item);
+
+            if self.window.len() > self.size {
+                let old = self.window.pop_front()?;
+                let count = self.counts.get_mut(&old)?;
+                *count -= 1;
+                match *count {
+                    0 => {
+                        self.counts.remove(&old);
+                    }
+                    1 => self.repeated -= 1,
+                    _ => {}
+                }
+            }
+
+            if self.window.len() == self.size && self.repeated == 0 {
+                return Some(self.index);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_pairwise() {
+    assert_eq!(
+        vec![(1, 2), (2, 3), (3, 0)],
+        [1, 2, 3, 0].into_iter().pairwise().collect::<Vec<_>>()
+    );
+    assert_eq!(0, [1].into_iter().pairwise().count());
+}
+
+#[test]
+fn test_rolling_sum() {
+    assert_eq!(
+        vec![6, 5, 7],
+        [1, 2, 3, 0, 4]
+            .into_iter()
+            .rolling_sum(3)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(0, [1, 2].into_iter().rolling_sum(3).count());
+}
+
+#[test]
+fn test_rolling_extremes() {
+    let numbers = [4, 2, 12, 3, 8, 5, 1, 7];
+    assert_eq!(
+        vec![2, 2, 3, 3, 1, 1],
+        numbers.into_iter().rolling_min(3).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![12, 12, 12, 8, 8, 7],
+        numbers.into_iter().rolling_max(3).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        numbers.to_vec(),
+        numbers.into_iter().rolling_max(1).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_distinct_windows() {
+    assert_eq!(
+        Some(5),
+        "bvwbjplbgvbhsrlpgdmjqwftvncz"
+            .chars()
+            .distinct_windows(4)
+            .next()
+    );
+    assert_eq!(
+        vec![5, 6, 7],
+        "accadfa".chars().distinct_windows(3).collect::<Vec<_>>()
+    );
+    assert_eq!(None, "aaaa".chars().distinct_windows(2).next());
+    assert_eq!(
+        vec![2, 4, 5, 7],
+        "abbcaab".chars().distinct_windows(2).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+#[should_panic(expected = "window size must be positive")]
+fn test_distinct_windows_empty() {
+    "abc".chars().distinct_windows(0);
+}
+
+#[test]
+fn test_distinct_windows_memory() {
+    let mut windows = (0..10_000).map(|i| i % 97).distinct_windows(5);
+    assert_eq!(Some(5), windows.next());
+    assert_eq!(9_995, windows.by_ref().count());
+    assert!(windows.window.len() <= 5);
+    assert!(windows.counts.len() <= 5);
+}